@@ -0,0 +1,84 @@
+use anyhow::{Error, Result};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Forest {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl Forest {
+    pub fn try_new(rows: Vec<Vec<bool>>) -> Result<Self> {
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(Error::msg("Forest has no columns"));
+        }
+        if let Some(y) = rows.iter().position(|row| row.len() != width) {
+            return Err(Error::msg(format!(
+                "Row {y} has width {}, expected {width}",
+                rows[y].len()
+            )));
+        }
+
+        let words_per_row = width.div_ceil(WORD_BITS);
+        let words = rows
+            .iter()
+            .flat_map(|row| {
+                row.chunks(WORD_BITS).map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, tree)| **tree)
+                        .fold(0u64, |word, (idx, _)| word | (1 << idx))
+                })
+            })
+            .collect::<Vec<u64>>();
+
+        Ok(Self {
+            width,
+            height: rows.len(),
+            words_per_row,
+            words,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_tree(&self, x: usize, y: usize) -> bool {
+        let word = self.words[y * self.words_per_row + x / WORD_BITS];
+        (word >> (x % WORD_BITS)) & 1 == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packing() -> Result<()> {
+        let row = (0..130).map(|x| x % 3 == 0).collect::<Vec<bool>>();
+        let forest = Forest::try_new(vec![row.clone(), row.iter().map(|b| !b).collect()])?;
+
+        assert_eq!(forest.width(), 130);
+        assert_eq!(forest.height(), 2);
+        assert!((0..130).all(|x| forest.is_tree(x, 0) == row[x]));
+        assert!((0..130).all(|x| forest.is_tree(x, 1) != row[x]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        assert!(Forest::try_new(vec![vec![true, false], vec![true]]).is_err());
+        assert!(Forest::try_new(vec![]).is_err());
+    }
+}
@@ -1,10 +1,19 @@
-use anyhow::Result;
+#![cfg_attr(test, feature(test))]
+
+use anyhow::{Error, Result};
+use forest::Forest;
 use nom::{
     character::{complete::line_ending, complete::one_of},
-    combinator::map,
+    combinator::{map, map_res},
     multi::{many1, separated_list1},
     IResult, Parser,
 };
+use slope::Slope;
+use std::{env, path::Path};
+
+mod forest;
+mod render;
+mod slope;
 
 const DATA: &str = include_str!("input.txt");
 
@@ -21,41 +30,75 @@ fn main() -> Result<()> {
     println!("Result part two: {result}");
     println!("Time spent: {took}");
 
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let laps = match args.iter().skip_while(|arg| *arg != "--laps").nth(1) {
+        Some(laps) => laps.parse::<usize>()?,
+        None => 1,
+    };
+    for (idx, _) in args.iter().enumerate().filter(|(_, arg)| *arg == "--slope") {
+        let slope = args
+            .get(idx + 1)
+            .ok_or_else(|| Error::msg("--slope needs a value"))?
+            .parse::<Slope>()?;
+        let result = slope.traverse(&input, laps);
+        println!("Result slope {slope} over {laps} lap(s): {result}");
+    }
+    if args.iter().any(|arg| arg == "--render") {
+        for (x_step, y_step) in SLOPES {
+            let grid = render::overlay(&input, x_step, y_step);
+            println!("Right {x_step}, down {y_step}:");
+            println!("{}", render::to_ascii(&grid));
+        }
+    }
+    if let Some(dir) = args.iter().skip_while(|arg| *arg != "--ppm").nth(1) {
+        for (x_step, y_step) in SLOPES {
+            let grid = render::overlay(&input, x_step, y_step);
+            let path = Path::new(dir).join(format!("slope_{x_step}_{y_step}.ppm"));
+            render::write_ppm(&grid, &path, 4)?;
+            println!("Written {}", path.display());
+        }
+    }
+
     Ok(())
 }
 
-fn part_one(input: &[Vec<bool>]) -> usize {
+fn part_one(input: &Forest) -> usize {
     traverse(input, 3, 1)
 }
 
-fn part_two(input: &[Vec<bool>]) -> usize {
-    let count = traverse(input, 1, 1);
-    let count_2 = traverse(input, 3, 1);
-    let count_3 = traverse(input, 5, 1);
-    let count_4 = traverse(input, 7, 1);
-    let count_5 = traverse(input, 1, 2);
+const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
 
-    count * count_2 * count_3 * count_4 * count_5
+fn part_two(input: &Forest) -> usize {
+    SLOPES
+        .iter()
+        .map(|(x_step, y_step)| traverse(input, *x_step, *y_step))
+        .product()
 }
 
-fn traverse(input: &[Vec<bool>], x_step: usize, y_step: usize) -> usize {
-    let line_length = input[0].len();
+fn traverse(input: &Forest, x_step: usize, y_step: usize) -> usize {
+    count_trees(input, x_step as i64, y_step, 1)
+}
 
-    let (_, count) = input
-        .iter()
-        .skip(y_step)
-        .step_by(y_step)
-        .fold((0, 0), |(x, count), line| {
-            let x = (x + x_step) % line_length;
-            let count = if line[x] { count + 1 } else { count };
+fn count_trees(input: &Forest, right: i64, down: usize, laps: usize) -> usize {
+    let line_length = input.width() as i64;
+
+    let (_, count) = (down..input.height() * laps)
+        .step_by(down)
+        .fold((0, 0), |(x, count), y| {
+            let x = (x + right).rem_euclid(line_length);
+            let count = if input.is_tree(x as usize, y % input.height()) {
+                count + 1
+            } else {
+                count
+            };
             (x, count)
         });
 
     count
 }
 
-fn parse(input: &str) -> IResult<&str, Vec<Vec<bool>>> {
-    separated_list1(line_ending, parse_line).parse(input)
+fn parse(input: &str) -> IResult<&str, Forest> {
+    map_res(separated_list1(line_ending, parse_line), Forest::try_new).parse(input)
 }
 
 fn parse_line(input: &str) -> IResult<&str, Vec<bool>> {
@@ -66,7 +109,7 @@ fn parse_position(input: &str) -> IResult<&str, bool> {
     map(one_of(".#"), |x| x == '#').parse(input)
 }
 
-fn parse_input(input: &'static str) -> Result<Vec<Vec<bool>>> {
+fn parse_input(input: &'static str) -> Result<Forest> {
     let (_, input) = parse(input)?;
 
     Ok(input)
@@ -74,7 +117,10 @@ fn parse_input(input: &'static str) -> Result<Vec<Vec<bool>>> {
 
 #[cfg(test)]
 mod tests {
+    extern crate test;
+
     use super::*;
+    use test::Bencher;
 
     const TESTDATA: &str = include_str!("test.txt");
 
@@ -105,4 +151,100 @@ mod tests {
 
         Ok(())
     }
+
+    fn traverse_vec(input: &[Vec<bool>], x_step: usize, y_step: usize) -> usize {
+        let line_length = input[0].len();
+
+        let (_, count) =
+            input
+                .iter()
+                .skip(y_step)
+                .step_by(y_step)
+                .fold((0, 0), |(x, count), line| {
+                    let x = (x + x_step) % line_length;
+                    let count = if line[x] { count + 1 } else { count };
+                    (x, count)
+                });
+
+        count
+    }
+
+    // The test map tiled to `width` by `height`, shifted one column to the right
+    // each time it repeats downwards.
+    fn synthetic_rows(width: usize, height: usize) -> Vec<Vec<bool>> {
+        let tile = TESTDATA
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect::<Vec<Vec<bool>>>();
+        (0..height)
+            .map(|y| {
+                let row = &tile[y % tile.len()];
+                (0..width)
+                    .map(|x| row[(x + row.len() - y / tile.len() % row.len()) % row.len()])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_traverse_synthetic() -> Result<()> {
+        let rows = synthetic_rows(200, 1_000);
+        let forest = Forest::try_new(rows.clone())?;
+        for (x_step, y_step) in SLOPES {
+            assert_eq!(
+                traverse(&forest, x_step, y_step),
+                traverse_vec(&rows, x_step, y_step)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[bench]
+    fn bench_traverse_forest_tall(b: &mut Bencher) -> Result<()> {
+        let forest = Forest::try_new(synthetic_rows(31, 100_000))?;
+        b.iter(|| {
+            SLOPES
+                .iter()
+                .map(|(x_step, y_step)| traverse(&forest, *x_step, *y_step))
+                .sum::<usize>()
+        });
+
+        Ok(())
+    }
+
+    #[bench]
+    fn bench_traverse_vec_tall(b: &mut Bencher) {
+        let rows = synthetic_rows(31, 100_000);
+        b.iter(|| {
+            SLOPES
+                .iter()
+                .map(|(x_step, y_step)| traverse_vec(&rows, *x_step, *y_step))
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    fn bench_traverse_forest_wide(b: &mut Bencher) -> Result<()> {
+        let forest = Forest::try_new(synthetic_rows(2_048, 5_000))?;
+        b.iter(|| {
+            SLOPES
+                .iter()
+                .map(|(x_step, y_step)| traverse(&forest, *x_step, *y_step))
+                .sum::<usize>()
+        });
+
+        Ok(())
+    }
+
+    #[bench]
+    fn bench_traverse_vec_wide(b: &mut Bencher) {
+        let rows = synthetic_rows(2_048, 5_000);
+        b.iter(|| {
+            SLOPES
+                .iter()
+                .map(|(x_step, y_step)| traverse_vec(&rows, *x_step, *y_step))
+                .sum::<usize>()
+        });
+    }
 }
@@ -0,0 +1,128 @@
+use crate::forest::Forest;
+use anyhow::Result;
+use std::{fs::File, io::Write, path::Path};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cell {
+    Open,
+    Tree,
+    VisitedOpen,
+    VisitedTree,
+}
+
+impl Cell {
+    pub fn as_char(&self) -> char {
+        match self {
+            Cell::Open => '.',
+            Cell::Tree => '#',
+            Cell::VisitedOpen => 'O',
+            Cell::VisitedTree => 'X',
+        }
+    }
+
+    pub fn as_rgb(&self) -> [u8; 3] {
+        match self {
+            Cell::Open => [255, 255, 255],
+            Cell::Tree => [34, 102, 34],
+            Cell::VisitedOpen => [80, 140, 255],
+            Cell::VisitedTree => [220, 30, 30],
+        }
+    }
+}
+
+pub fn overlay(input: &Forest, x_step: usize, y_step: usize) -> Vec<Vec<Cell>> {
+    let line_length = input.width();
+    let steps = (input.height() - 1) / y_step;
+    let tiles = (x_step * steps + 1).div_ceil(line_length);
+
+    let mut grid = (0..input.height())
+        .map(|y| {
+            (0..tiles * line_length)
+                .map(|x| {
+                    if input.is_tree(x % line_length, y) {
+                        Cell::Tree
+                    } else {
+                        Cell::Open
+                    }
+                })
+                .collect::<Vec<Cell>>()
+        })
+        .collect::<Vec<Vec<Cell>>>();
+
+    (1..=steps).for_each(|step| {
+        let cell = &mut grid[step * y_step][step * x_step];
+        *cell = match cell {
+            Cell::Tree => Cell::VisitedTree,
+            _ => Cell::VisitedOpen,
+        };
+    });
+
+    grid
+}
+
+pub fn to_ascii(grid: &[Vec<Cell>]) -> String {
+    grid.iter()
+        .map(|line| line.iter().map(Cell::as_char).collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn write_ppm(grid: &[Vec<Cell>], path: &Path, scale: usize) -> Result<()> {
+    let width = grid[0].len() * scale;
+    let height = grid.len() * scale;
+
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for line in grid {
+        let row = line
+            .iter()
+            .flat_map(|cell| cell.as_rgb().repeat(scale))
+            .collect::<Vec<u8>>();
+        for _ in 0..scale {
+            pixels.extend_from_slice(&row);
+        }
+    }
+
+    let mut file = File::create(path)?;
+    write!(file, "P6\n{width} {height}\n255\n")?;
+    file.write_all(&pixels)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, traverse, SLOPES};
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_overlay_testdata() -> Result<()> {
+        let ascii = to_ascii(&overlay(&parse_input(TESTDATA)?, 3, 1));
+        let lines = ascii.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "..##.........##.........##.......");
+        assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+        assert_eq!(lines[2], ".#....X..#..#....#..#..#....#..#.");
+        assert_eq!(lines[10], ".#..#...#.#.#..#...#.#.#..#...X.#");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlay_counts_testdata() -> Result<()> {
+        let input = parse_input(TESTDATA)?;
+        for (x_step, y_step) in SLOPES {
+            let grid = overlay(&input, x_step, y_step);
+            let hits = grid
+                .iter()
+                .flatten()
+                .filter(|cell| **cell == Cell::VisitedTree)
+                .count();
+            assert_eq!(hits, traverse(&input, x_step, y_step));
+        }
+
+        Ok(())
+    }
+}
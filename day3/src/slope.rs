@@ -0,0 +1,115 @@
+use crate::{count_trees, forest::Forest};
+use anyhow::{Error, Result};
+use std::{fmt, str::FromStr};
+
+// A slope of `right / down` columns per row. The toboggan only stops on
+// squares the line passes through exactly, so the ratio is kept in lowest
+// terms: 2 right per 4 down visits every second row, not every fourth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slope {
+    right: i64,
+    down: usize,
+}
+
+impl Slope {
+    pub fn new(right: i64, down: i64) -> Result<Self> {
+        if down <= 0 {
+            return Err(Error::msg("Slope must move down"));
+        }
+        let divisor = gcd(right.unsigned_abs(), down.unsigned_abs()) as i64;
+
+        Ok(Self {
+            right: right / divisor,
+            down: (down / divisor) as usize,
+        })
+    }
+
+    pub fn traverse(&self, input: &Forest, laps: usize) -> usize {
+        count_trees(input, self.right, self.down, laps)
+    }
+}
+
+impl FromStr for Slope {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (right, down) = s.split_once('/').unwrap_or((s, "1"));
+        Slope::new(right.trim().parse()?, down.trim().parse()?)
+    }
+}
+
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.right, self.down)
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!("3".parse::<Slope>()?, Slope::new(3, 1)?);
+        assert_eq!("1/3".parse::<Slope>()?, Slope::new(1, 3)?);
+        assert_eq!("-2".parse::<Slope>()?, Slope::new(-2, 1)?);
+        assert_eq!("2/4".parse::<Slope>()?.to_string(), "1/2");
+        assert_eq!("-4/6".parse::<Slope>()?.to_string(), "-2/3");
+        assert!("1/0".parse::<Slope>().is_err());
+        assert!("1/-1".parse::<Slope>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_traverse_testdata() -> Result<()> {
+        let input = parse_input(TESTDATA)?;
+
+        assert_eq!("3".parse::<Slope>()?.traverse(&input, 1), 7);
+        assert_eq!("1/2".parse::<Slope>()?.traverse(&input, 1), 2);
+        assert_eq!("2/4".parse::<Slope>()?.traverse(&input, 1), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_traverse_left_testdata() -> Result<()> {
+        let rows = TESTDATA
+            .lines()
+            .map(|line| {
+                let line = line.chars().map(|c| c == '#').collect::<Vec<bool>>();
+                (0..line.len())
+                    .map(|x| line[(line.len() - x) % line.len()])
+                    .collect::<Vec<bool>>()
+            })
+            .collect::<Vec<Vec<bool>>>();
+        let mirrored = Forest::try_new(rows)?;
+
+        assert_eq!("-3".parse::<Slope>()?.traverse(&mirrored, 1), 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_traverse_laps_testdata() -> Result<()> {
+        let input = parse_input(TESTDATA)?;
+        let slope = "0".parse::<Slope>()?;
+
+        assert_eq!(slope.traverse(&input, 1), 3);
+        assert_eq!(slope.traverse(&input, 2), 6);
+        assert_eq!(slope.traverse(&input, 3), 9);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,533 @@
+use crate::{line_of, line_starts, parse_batch, Key, Passport};
+use anyhow::{Error, Result};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::{char, line_ending},
+    combinator::{all_consuming, map, opt},
+    multi::{many0, separated_list1},
+    sequence::{delimited, terminated},
+    IResult, Parser,
+};
+use serde_json::{Map, Value as JsonValue};
+use std::str::FromStr;
+
+const KNOWN_KEYS: [Key; 8] = [
+    Key::Byr,
+    Key::Iyr,
+    Key::Eyr,
+    Key::Hgt,
+    Key::Hcl,
+    Key::Ecl,
+    Key::Pid,
+    Key::Cid,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Native,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(Format::Native),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(Error::msg(format!("Unknown format {s}"))),
+        }
+    }
+}
+
+pub fn import(format: Format, input: &str) -> Result<Vec<Passport>> {
+    match format {
+        Format::Native => from_native(input),
+        Format::Json => from_json(input),
+        Format::Csv => from_csv(input),
+    }
+}
+
+pub fn export(format: Format, input: &[Passport]) -> Result<String> {
+    match format {
+        Format::Native => to_native(input),
+        Format::Json => to_json(input),
+        Format::Csv => to_csv(input),
+    }
+}
+
+fn from_native(input: &str) -> Result<Vec<Passport>> {
+    parse_batch(input)
+}
+
+// Whitespace separates the pairs and `:` the key from its value, so neither can
+// be written inside a key or value, and a key cannot be empty.
+fn to_native(input: &[Passport]) -> Result<String> {
+    for passport in input {
+        for field in &passport.fields {
+            let key = field.key.as_str();
+            if key.is_empty() || key.contains(|c: char| c == ':' || c.is_whitespace()) {
+                return Err(Error::msg(format!(
+                    "Passport {}: key {key:?} cannot be written as key:value",
+                    passport.index
+                )));
+            }
+            if field.raw.contains(char::is_whitespace) {
+                return Err(Error::msg(format!(
+                    "Passport {}: value {:?} of {key} cannot be written as key:value",
+                    passport.index, field.raw
+                )));
+            }
+        }
+    }
+
+    Ok(input
+        .iter()
+        .map(|passport| {
+            passport
+                .fields
+                .iter()
+                .map(|field| format!("{}:{}", field.key.as_str(), field.raw))
+                .chain(passport.malformed.iter().map(|(_, token)| token.clone()))
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n"))
+}
+
+fn from_json(input: &str) -> Result<Vec<Passport>> {
+    let records = serde_json::from_str::<Vec<Map<String, JsonValue>>>(input)?;
+
+    records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let scalar = |key: &str, value: &JsonValue| match value {
+                JsonValue::String(value) => Ok(value.clone()),
+                JsonValue::Number(value) => Ok(value.to_string()),
+                _ => Err(Error::msg(format!(
+                    "Passport {index}: {key} must be a string, a number or an array of those"
+                ))),
+            };
+            // A key that occurs more than once holds an array of its values.
+            let values = record
+                .iter()
+                .flat_map(|(key, value)| match value {
+                    JsonValue::Array(values) => values
+                        .iter()
+                        .map(|value| Ok((key.as_str(), scalar(key, value)?)))
+                        .collect::<Vec<Result<(&str, String)>>>(),
+                    value => vec![scalar(key, value).map(|value| (key.as_str(), value))],
+                })
+                .collect::<Result<Vec<(&str, String)>>>()?;
+            let pairs = values
+                .iter()
+                .map(|(key, value)| (None, *key, Some(value.as_str())))
+                .collect();
+            Ok(Passport::new(index, pairs))
+        })
+        .collect()
+}
+
+fn location(index: usize, line: Option<usize>) -> String {
+    match line {
+        Some(line) => format!("Passport {index} (line {line})"),
+        None => format!("Passport {index}"),
+    }
+}
+
+// JSON and CSV only hold `key:value` pairs, so tokens without a value cannot be exported.
+fn check_malformed(input: &[Passport]) -> Result<()> {
+    match input
+        .iter()
+        .find_map(|passport| Some((passport.index, passport.malformed.first()?)))
+    {
+        Some((index, (line, token))) => Err(Error::msg(format!(
+            "{}: {token:?} is not a key:value pair",
+            location(index, *line)
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn to_json(input: &[Passport]) -> Result<String> {
+    check_malformed(input)?;
+    let records = input
+        .iter()
+        .map(|passport| {
+            let mut record = Map::new();
+            for field in &passport.fields {
+                let value = JsonValue::String(field.raw.clone());
+                match record.get_mut(field.key.as_str()) {
+                    None => {
+                        record.insert(field.key.as_str().to_string(), value);
+                    }
+                    Some(JsonValue::Array(values)) => values.push(value),
+                    Some(first) => *first = JsonValue::Array(vec![first.take(), value]),
+                }
+            }
+            record
+        })
+        .collect::<Vec<Map<String, JsonValue>>>();
+
+    Ok(serde_json::to_string_pretty(&records)?)
+}
+
+fn from_csv(input: &str) -> Result<Vec<Passport>> {
+    let (_, rows) = all_consuming(terminated(parse_csv, opt(line_ending)))
+        .parse(input)
+        .map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => Error::msg(format!(
+                "Line {}: cannot parse CSV",
+                line_of(input, &line_starts(input), e.input)
+            )),
+            nom::Err::Incomplete(_) => Error::msg("Incomplete CSV"),
+        })?;
+    let ((_, header), rows) = rows
+        .split_first()
+        .ok_or_else(|| Error::msg("CSV has no header"))?;
+    let line_starts = line_starts(input);
+
+    // A passport's index is its row number, counting blank rows too.
+    rows.iter()
+        .enumerate()
+        .filter(|(_, (_, row))| row.iter().any(|cell| !cell.is_empty()))
+        .map(|(index, (start, row))| {
+            let line = line_of(input, &line_starts, start);
+            if row.len() != header.len() {
+                return Err(Error::msg(format!(
+                    "Line {line}: expected {} columns, got {}",
+                    header.len(),
+                    row.len()
+                )));
+            }
+            let pairs = header
+                .iter()
+                .zip(row)
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(key, value)| (Some(line), key.as_str(), Some(value.as_str())))
+                .collect();
+            Ok(Passport::new(index, pairs))
+        })
+        .collect()
+}
+
+fn to_csv(input: &[Passport]) -> Result<String> {
+    check_malformed(input)?;
+    // An empty cell is an absent field, so an empty value would not read back.
+    if let Some((passport, field)) = input.iter().find_map(|passport| {
+        let field = passport.fields.iter().find(|field| field.raw.is_empty())?;
+        Some((passport, field))
+    }) {
+        return Err(Error::msg(format!(
+            "{}: empty value of {} cannot be written to CSV",
+            location(passport.index, field.line),
+            field.key.as_str()
+        )));
+    }
+    // A key that occurs more than once in a passport gets a column per occurrence.
+    let mut columns = KNOWN_KEYS
+        .iter()
+        .map(|key| (key.clone(), 1))
+        .collect::<Vec<(Key, usize)>>();
+    for passport in input {
+        for field in &passport.fields {
+            let occurrences = passport
+                .fields
+                .iter()
+                .filter(|other| other.key == field.key)
+                .count();
+            match columns.iter_mut().find(|(key, _)| *key == field.key) {
+                Some((_, count)) => *count = (*count).max(occurrences),
+                None => columns.push((field.key.clone(), occurrences)),
+            }
+        }
+    }
+
+    let header = columns
+        .iter()
+        .flat_map(|(key, count)| vec![quote_csv(key.as_str()); *count])
+        .collect::<Vec<String>>()
+        .join(",");
+    let rows = input.iter().map(|passport| {
+        columns
+            .iter()
+            .flat_map(|(key, count)| {
+                let mut values = passport
+                    .fields
+                    .iter()
+                    .filter(|field| field.key == *key)
+                    .map(|field| quote_csv(&field.raw))
+                    .collect::<Vec<String>>();
+                values.resize(*count, String::new());
+                values
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    });
+
+    Ok(std::iter::once(header)
+        .chain(rows)
+        .map(|line| line + "\n")
+        .collect())
+}
+
+fn quote_csv(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_csv(input: &str) -> IResult<&str, Vec<(&str, Vec<String>)>> {
+    separated_list1(line_ending, parse_csv_row).parse(input)
+}
+
+// The cells of a row along with the input it starts at, to tell its line.
+fn parse_csv_row(input: &str) -> IResult<&str, (&str, Vec<String>)> {
+    map(
+        separated_list1(char(','), alt((parse_quoted_cell, parse_cell))),
+        |cells| (input, cells),
+    )
+    .parse(input)
+}
+
+fn parse_cell(input: &str) -> IResult<&str, String> {
+    map(take_while(|c: char| !",\"\r\n".contains(c)), String::from).parse(input)
+}
+
+fn parse_quoted_cell(input: &str) -> IResult<&str, String> {
+    map(
+        delimited(
+            char('"'),
+            many0(alt((map(tag("\"\""), |_| "\""), take_while1(|c| c != '"')))),
+            char('"'),
+        ),
+        |parts| parts.concat(),
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, schema::Schema, ViolationKind};
+
+    const TESTDATA: &str = include_str!("test.txt");
+    const TESTDATA2: &str = include_str!("test2.txt");
+
+    fn records(input: &[Passport]) -> Vec<Vec<(Key, String)>> {
+        input
+            .iter()
+            .map(|passport| {
+                let mut fields = passport
+                    .fields
+                    .iter()
+                    .map(|field| (field.key.clone(), field.raw.clone()))
+                    .collect::<Vec<(Key, String)>>();
+                fields.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+                fields
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_json_testdata() -> Result<()> {
+        for data in [TESTDATA, TESTDATA2] {
+            let input = parse_input(data)?;
+            let json = export(Format::Json, &input)?;
+            let native = export(Format::Native, &import(Format::Json, &json)?)?;
+
+            assert_eq!(records(&import(Format::Native, &native)?), records(&input));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_csv_testdata() -> Result<()> {
+        for data in [TESTDATA, TESTDATA2] {
+            let input = parse_input(data)?;
+            let csv = export(Format::Csv, &input)?;
+
+            assert_eq!(records(&import(Format::Csv, &csv)?), records(&input));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_layout() -> Result<()> {
+        let input = parse_input("byr:1937 hgt:183cm x,y:\"a\"\n\niyr:2017")?;
+        let csv = export(Format::Csv, &input)?;
+
+        assert_eq!(
+            csv,
+            "byr,iyr,eyr,hgt,hcl,ecl,pid,cid,\"x,y\"\n\
+             1937,,,183cm,,,,,\"\"\"a\"\"\"\n\
+             ,2017,,,,,,,\n"
+        );
+        assert_eq!(records(&import(Format::Csv, &csv)?), records(&input));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_csv_line_breaks() -> Result<()> {
+        let input = import(
+            Format::Json,
+            r#"[{ "byr": "1937", "hcl": "a\nb", "a\r\nb": "c" }]"#,
+        )?;
+        let csv = export(Format::Csv, &input)?;
+
+        assert!(csv.contains(",\"a\r\nb\"\n1937,"));
+        assert!(csv.contains(",\"a\nb\","));
+        assert_eq!(records(&import(Format::Csv, &csv)?), records(&input));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_duplicate_keys() -> Result<()> {
+        let input = parse_input("byr:1937 iyr:2017 byr:1940 byr:1950\n\nbyr:1937")?;
+        let json = export(Format::Json, &input)?;
+        let csv = export(Format::Csv, &input)?;
+
+        assert!(
+            json.contains("\"byr\": [\n      \"1937\",\n      \"1940\",\n      \"1950\"\n    ]")
+        );
+        assert!(csv.starts_with("byr,byr,byr,iyr,"));
+        assert!(csv.contains("\n1937,,,,"));
+        for format in [Format::Json, Format::Csv] {
+            let output = import(format, &export(format, &input)?)?;
+
+            assert_eq!(records(&output), records(&input));
+            assert_eq!(
+                output[0].validate(&Schema::default()).len(),
+                input[0].validate(&Schema::default()).len()
+            );
+            assert!(output[0]
+                .validate(&Schema::default())
+                .iter()
+                .any(|violation| { matches!(violation.kind, ViolationKind::DuplicateKey { .. }) }));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_native_rejects_separators() -> Result<()> {
+        for json in [
+            r#"[{ "hcl": "a b" }]"#,
+            r#"[{ "hcl": "a\nb" }]"#,
+            r#"[{ "h cl": "a" }]"#,
+            r#"[{ "h:cl": "a" }]"#,
+        ] {
+            assert!(export(Format::Native, &import(Format::Json, json)?).is_err());
+        }
+        let input = import(Format::Json, r#"[{ "hcl": "a:b" }]"#)?;
+        assert_eq!(export(Format::Native, &input)?, "hcl:a:b");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_empty_value() -> Result<()> {
+        let input = parse_input("hcl: byr:1937")?;
+        let native = export(Format::Native, &input)?;
+
+        assert_eq!(native, "hcl: byr:1937");
+        assert_eq!(records(&import(Format::Native, &native)?), records(&input));
+        assert_eq!(
+            records(&import(Format::Json, &export(Format::Json, &input)?)?),
+            records(&input)
+        );
+        assert_eq!(
+            export(Format::Csv, &input).err().map(|e| e.to_string()),
+            Some(String::from(
+                "Passport 0 (line 1): empty value of hcl cannot be written to CSV"
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_malformed() -> Result<()> {
+        let input = parse_input("byr:1937 foo\n\niyr:2017")?;
+
+        assert_eq!(export(Format::Native, &input)?, "byr:1937 foo\n\niyr:2017");
+        assert!(export(Format::Json, &input).is_err());
+        assert!(export(Format::Csv, &input).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_rejects_leftover_input() {
+        let error = |format, input| import(format, input).err().map(|e| e.to_string());
+
+        assert_eq!(
+            error(Format::Csv, "byr,hcl\n1937,\"#ab"),
+            Some(String::from("Line 2: cannot parse CSV"))
+        );
+        assert_eq!(error(Format::Csv, "byr,hcl\n1937,#abcdef\n"), None);
+        assert_eq!(
+            error(Format::Native, "byr:1937\n\n\n\niyr:2017"),
+            Some(String::from("Line 5: cannot parse \"iyr:2017\""))
+        );
+    }
+
+    #[test]
+    fn test_import_lines() -> Result<()> {
+        let input = import(Format::Csv, "byr,hcl\n1937,\"a\nb\"\n,\n1940,#abcdef\n")?;
+        let schema = Schema::default();
+
+        assert_eq!(
+            input
+                .iter()
+                .map(|passport| passport.index)
+                .collect::<Vec<usize>>(),
+            vec![0, 2]
+        );
+        assert_eq!(input[1].get(&Key::Byr).unwrap().line, Some(5));
+        assert!(input[1].validate(&schema)[0]
+            .to_string()
+            .starts_with("passport 2 (line 5): "));
+        assert_eq!(
+            import(Format::Csv, "byr,hcl\n1937,#abcdef\n\n1940\n")
+                .err()
+                .map(|e| e.to_string()),
+            Some(String::from("Line 4: expected 2 columns, got 1"))
+        );
+
+        let input = import(Format::Json, r#"[{ "byr": 1937 }, { "byr": 1940 }]"#)?;
+        assert_eq!(input[1].get(&Key::Byr).unwrap().line, None);
+        assert!(input[1].validate(&schema)[0]
+            .to_string()
+            .starts_with("passport 1: "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_json() -> Result<()> {
+        let input = import(
+            Format::Json,
+            r#"[{ "byr": 1937, "ecl": "gry" }, { "pid": "860033327" }]"#,
+        )?;
+
+        assert_eq!(input.len(), 2);
+        assert_eq!(input[0].get(&Key::Byr).unwrap().raw, "1937");
+        assert_eq!(input[1].get(&Key::Pid).unwrap().raw, "860033327");
+        assert!(import(Format::Json, r#"[{ "byr": [[1937]] }]"#).is_err());
+        assert!(import(Format::Json, r#"[{ "byr": { "year": 1937 } }]"#).is_err());
+
+        Ok(())
+    }
+}
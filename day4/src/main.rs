@@ -1,42 +1,88 @@
 use anyhow::{Error, Result};
 use nom::{
     branch::alt,
-    bytes::complete::take_while,
-    character::complete::{alpha1, char, line_ending, space1},
-    combinator::map_res,
+    bytes::complete::{take_while, take_while1},
+    character::complete::{char, line_ending, space1},
+    combinator::map,
     multi::separated_list1,
     sequence::{pair, separated_pair},
-    AsChar, IResult, Parser,
+    IResult, Offset, Parser,
 };
-use std::collections::HashMap;
+use report::{Report, Violation, ViolationKind};
+use schema::{Mode, Schema};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{env, fs, iter, path::Path};
+use value::{Value, ValueError};
+
+mod convert;
+mod report;
+mod schema;
+mod value;
 
 const DATA: &str = include_str!("input.txt");
 
 fn main() -> Result<()> {
-    let (took, result) = took::took(|| parse_input(DATA));
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let import = args
+        .iter()
+        .position(|arg| arg == "--import")
+        .map(|idx| (args.get(idx + 1), args.get(idx + 2)));
+
+    let (took, result) = took::took(|| match import {
+        Some((Some(format), Some(path))) => {
+            convert::import(format.parse()?, &fs::read_to_string(path)?)
+        }
+        Some(_) => Err(Error::msg("--import needs a format and a path")),
+        None => parse_input(DATA),
+    });
     println!("Time spent parsing: {}", took);
-    let input = result?;
+    let mut input = result?;
+
+    let mut schema = match args.iter().skip_while(|arg| *arg != "--schema").nth(1) {
+        Some(path) => Schema::load(Path::new(path))?,
+        None => Schema::default(),
+    };
+    if args.iter().any(|arg| arg == "--lenient") {
+        schema.set_mode(Mode::Lenient);
+    }
+    for passport in &mut input {
+        passport.resolve(&schema);
+    }
 
-    let (took, result) = took::took(|| part_one(&input));
+    let (took, result) = took::took(|| part_one(&input, &schema));
     println!("Result part one: {result}");
     println!("Time spent: {took}");
 
-    let (took, result) = took::took(|| part_two(&input));
+    let (took, result) = took::took(|| part_two(&input, &schema));
     println!("Result part two: {result}");
     println!("Time spent: {took}");
 
+    if let Some(format) = args.iter().skip_while(|arg| *arg != "--report").nth(1) {
+        let report = Report::new(&input, &schema);
+        match format.as_str() {
+            "text" => print!("{}", report.to_text()),
+            "json" => println!("{}", report.to_json()?),
+            _ => return Err(Error::msg(format!("Unknown report format {format}"))),
+        }
+    }
+
+    if let Some(format) = args.iter().skip_while(|arg| *arg != "--export").nth(1) {
+        println!("{}", convert::export(format.parse()?, &input)?);
+    }
+
     Ok(())
 }
 
-fn part_one(input: &[Passport]) -> usize {
-    input.iter().filter(|p| p.is_complete()).count()
+fn part_one(input: &[Passport], schema: &Schema) -> usize {
+    input.iter().filter(|p| p.is_complete(schema)).count()
 }
 
-fn part_two(input: &[Passport]) -> usize {
-    input.iter().filter(|p| p.is_valid()).count()
+fn part_two(input: &[Passport], schema: &Schema) -> usize {
+    input.iter().filter(|p| p.is_valid(schema)).count()
 }
 
-#[derive(Hash, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Hash, Eq, PartialEq)]
+#[serde(from = "String")]
 enum Key {
     Byr,
     Iyr,
@@ -46,148 +92,246 @@ enum Key {
     Ecl,
     Pid,
     Cid,
+    Other(String),
 }
 
-impl TryFrom<&str> for Key {
-    type Error = Error;
+impl Key {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Key::Byr => "byr",
+            Key::Iyr => "iyr",
+            Key::Eyr => "eyr",
+            Key::Hgt => "hgt",
+            Key::Hcl => "hcl",
+            Key::Ecl => "ecl",
+            Key::Pid => "pid",
+            Key::Cid => "cid",
+            Key::Other(key) => key,
+        }
+    }
+}
 
-    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl From<&str> for Key {
+    fn from(value: &str) -> Self {
         match value {
-            "byr" => Ok(Key::Byr),
-            "iyr" => Ok(Key::Iyr),
-            "eyr" => Ok(Key::Eyr),
-            "hgt" => Ok(Key::Hgt),
-            "hcl" => Ok(Key::Hcl),
-            "ecl" => Ok(Key::Ecl),
-            "pid" => Ok(Key::Pid),
-            "cid" => Ok(Key::Cid),
-            _ => Err(Error::msg("Value not found")),
+            "byr" => Key::Byr,
+            "iyr" => Key::Iyr,
+            "eyr" => Key::Eyr,
+            "hgt" => Key::Hgt,
+            "hcl" => Key::Hcl,
+            "ecl" => Key::Ecl,
+            "pid" => Key::Pid,
+            "cid" => Key::Cid,
+            _ => Key::Other(value.to_string()),
         }
     }
 }
 
+impl From<String> for Key {
+    fn from(value: String) -> Self {
+        value.as_str().into()
+    }
+}
+
+// Lines are `None` for records imported from formats without lines, like JSON.
 struct Passport {
-    data: HashMap<Key, String>,
+    index: usize,
+    line: Option<usize>,
+    fields: Vec<Field>,
+    malformed: Vec<(Option<usize>, String)>,
 }
 
-impl Passport {
-    pub fn try_new(pairs: Vec<(&str, &str)>) -> Result<Self> {
-        let result = pairs
-            .iter()
-            .map(|(key, value)| {
-                let key: Key = (*key).try_into()?;
-                let value = value.to_string();
-                Ok((key, value))
-            })
-            .collect::<Result<Vec<(Key, String)>>>();
+struct Field {
+    key: Key,
+    raw: String,
+    line: Option<usize>,
+    // Set by `Passport::resolve` for the keys its schema declares.
+    value: Option<std::result::Result<Value, ValueError>>,
+}
 
-        match result {
-            Ok(vec) => {
-                let data = vec.into_iter().collect::<HashMap<Key, String>>();
-                Ok(Self { data })
+impl Passport {
+    // Tokens without a value are not `key:value` pairs; they are kept so that
+    // validation can report them.
+    pub fn new(index: usize, tokens: Vec<(Option<usize>, &str, Option<&str>)>) -> Self {
+        let line = tokens.first().and_then(|(line, _, _)| *line);
+        let mut fields = vec![];
+        let mut malformed = vec![];
+        for (line, key, raw) in tokens {
+            match raw {
+                Some(raw) => fields.push(Field {
+                    key: Key::from(key),
+                    raw: raw.to_string(),
+                    line,
+                    value: None,
+                }),
+                None => malformed.push((line, key.to_string())),
             }
-            Err(e) => Err(e),
         }
-    }
-
-    pub fn is_complete(&self) -> bool {
-        [
-            Key::Byr,
-            Key::Iyr,
-            Key::Eyr,
-            Key::Hgt,
-            Key::Hcl,
-            Key::Ecl,
-            Key::Pid,
-        ]
-        .iter()
-        .all(|key| self.data.contains_key(key))
-    }
 
-    pub fn is_valid(&self) -> bool {
-        self.is_complete()
-            && self.is_valid_byr()
-            && self.is_valid_iyr()
-            && self.is_valid_eyr()
-            && self.is_valid_hgt()
-            && self.is_valid_hcl()
-            && self.is_valid_ecl()
-            && self.is_valid_pid()
+        Self {
+            index,
+            line,
+            fields,
+            malformed,
+        }
     }
 
-    pub fn is_valid_byr(&self) -> bool {
-        self.data[&Key::Byr]
-            .parse::<u16>()
-            .is_ok_and(|byr| (1920u16..=2002u16).contains(&byr))
+    // Reads every field the schema declares with the grammar of its `FieldKind`.
+    pub fn resolve(&mut self, schema: &Schema) {
+        for field in &mut self.fields {
+            field.value = schema
+                .rule(&field.key)
+                .map(|rule| rule.kind.parse(&field.raw));
+        }
     }
 
-    pub fn is_valid_iyr(&self) -> bool {
-        self.data[&Key::Iyr]
-            .parse::<u16>()
-            .is_ok_and(|iyr| (2010u16..=2020u16).contains(&iyr))
+    pub fn get(&self, key: &Key) -> Option<&Field> {
+        self.fields.iter().find(|field| field.key == *key)
     }
 
-    pub fn is_valid_eyr(&self) -> bool {
-        self.data[&Key::Eyr]
-            .parse::<u16>()
-            .is_ok_and(|eyr| (2020u16..=2030u16).contains(&eyr))
+    pub fn is_complete(&self, schema: &Schema) -> bool {
+        schema.required_keys().all(|key| self.get(key).is_some())
     }
 
-    pub fn is_valid_hgt(&self) -> bool {
-        let hgt = self.data[&Key::Hgt].as_str();
-        let (height, unit) = hgt.split_at(hgt.len() - 2);
-
-        height.parse::<u16>().is_ok_and(|h| {
-            if unit == "cm" {
-                (150u16..=193u16).contains(&h)
-            } else if unit == "in" {
-                (59u16..=76u16).contains(&h)
-            } else {
-                false
-            }
-        })
+    pub fn is_valid(&self, schema: &Schema) -> bool {
+        self.validate(schema).is_empty()
     }
 
-    pub fn is_valid_hcl(&self) -> bool {
-        self.data[&Key::Hcl]
-            .strip_prefix('#')
-            .is_some_and(|hcl| u32::from_str_radix(hcl, 16).is_ok())
+    pub fn validate(&self, schema: &Schema) -> Vec<Violation> {
+        let rules = schema.fields().iter().filter_map(|rule| {
+            let (line, kind) = match self.get(&rule.key) {
+                None if rule.required => (self.line, ViolationKind::Missing),
+                None => return None,
+                Some(field) => (field.line, rule.check(field).err()?),
+            };
+            Some(Violation::new(self.index, line, rule.key.clone(), kind))
+        });
+
+        let unknown = self
+            .fields
+            .iter()
+            .filter(|field| schema.mode() == Mode::Strict && !schema.declares(&field.key))
+            .map(|field| {
+                Violation::new(
+                    self.index,
+                    field.line,
+                    field.key.clone(),
+                    ViolationKind::UnknownKey,
+                )
+            });
+
+        let duplicates = self.fields.iter().filter_map(|field| {
+            let first = self.get(&field.key)?;
+            (!std::ptr::eq(first, field)).then(|| {
+                Violation::new(
+                    self.index,
+                    field.line,
+                    field.key.clone(),
+                    ViolationKind::DuplicateKey {
+                        first_line: first.line,
+                    },
+                )
+            })
+        });
+
+        let malformed = self.malformed.iter().map(|(line, token)| {
+            Violation::new(
+                self.index,
+                *line,
+                Key::Other(token.clone()),
+                ViolationKind::Malformed,
+            )
+        });
+
+        rules
+            .chain(unknown)
+            .chain(duplicates)
+            .chain(malformed)
+            .collect()
     }
+}
 
-    pub fn is_valid_ecl(&self) -> bool {
-        ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&self.data[&Key::Ecl].as_str())
-    }
+// The offset at which each line starts, to look up line numbers with a binary search.
+fn line_starts(input: &str) -> Vec<usize> {
+    iter::once(0)
+        .chain(input.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect()
+}
 
-    pub fn is_valid_pid(&self) -> bool {
-        let pid = self.data[&Key::Pid].as_str();
-        pid.len() == 9 && pid.chars().all(|c| c.is_dec_digit())
-    }
+fn line_of(input: &str, line_starts: &[usize], fragment: &str) -> usize {
+    let offset = input.offset(fragment);
+    line_starts.partition_point(|start| *start <= offset)
 }
 
 fn parse(input: &str) -> IResult<&str, Vec<Passport>> {
-    separated_list1(pair(line_ending, line_ending), parse_passport).parse(input)
+    map(
+        separated_list1(pair(line_ending, line_ending), parse_passport),
+        |records| {
+            let line_starts = line_starts(input);
+            records
+                .into_iter()
+                .enumerate()
+                .map(|(index, pairs)| {
+                    let pairs = pairs
+                        .into_iter()
+                        .map(|(key, value)| (Some(line_of(input, &line_starts, key)), key, value))
+                        .collect();
+                    Passport::new(index, pairs)
+                })
+                .collect::<Vec<Passport>>()
+        },
+    )
+    .parse(input)
 }
 
-fn parse_passport(input: &str) -> IResult<&str, Passport> {
-    map_res(
-        separated_list1(alt((space1, line_ending)), parse_key_value),
-        Passport::try_new,
-    )
+fn parse_passport(input: &str) -> IResult<&str, Vec<(&str, Option<&str>)>> {
+    separated_list1(alt((space1, line_ending)), parse_token).parse(input)
+}
+
+fn parse_token(input: &str) -> IResult<&str, (&str, Option<&str>)> {
+    alt((
+        map(parse_key_value, |(key, value)| (key, Some(value))),
+        map(take_while1(|c: char| !c.is_whitespace()), |token| {
+            (token, None)
+        }),
+    ))
     .parse(input)
 }
 
 fn parse_key_value(input: &str) -> IResult<&str, (&str, &str)> {
-    separated_pair(alpha1, char(':'), parse_value).parse(input)
+    separated_pair(parse_key, char(':'), parse_value).parse(input)
+}
+
+fn parse_key(input: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c != ':' && !c.is_whitespace())(input)
 }
 
 fn parse_value(input: &str) -> IResult<&str, &str> {
-    take_while(|c: char| c.is_alphanumeric() || c == '#')(input)
+    take_while(|c: char| !c.is_whitespace())(input)
 }
 
-fn parse_input(input: &'static str) -> Result<Vec<Passport>> {
-    let (_, input) = parse(input)?;
+// Like `parse`, but anything left after the last passport other than
+// whitespace is an error.
+fn parse_batch(input: &str) -> Result<Vec<Passport>> {
+    let (rest, passports) = parse(input).map_err(|e| Error::msg(e.to_string()))?;
+    let rest = rest.trim_start();
+    if !rest.is_empty() {
+        let line = line_of(input, &line_starts(input), rest);
+        let text = rest.lines().next().unwrap_or_default();
+        return Err(Error::msg(format!("Line {line}: cannot parse {text:?}")));
+    }
 
-    Ok(input)
+    Ok(passports)
+}
+
+fn parse_input(input: &'static str) -> Result<Vec<Passport>> {
+    parse_batch(input)
 }
 
 #[cfg(test)]
@@ -199,28 +343,130 @@ mod tests {
 
     #[test]
     fn test_part_one_testdata() -> Result<()> {
-        assert_eq!(part_one(&parse_input(TESTDATA)?), 2);
+        assert_eq!(part_one(&parse_input(TESTDATA)?, &Schema::default()), 2);
 
         Ok(())
     }
 
     #[test]
     fn test_part_one() -> Result<()> {
-        assert_eq!(part_one(&parse_input(DATA)?), 235);
+        assert_eq!(part_one(&parse_input(DATA)?, &Schema::default()), 235);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two_testdata() -> Result<()> {
-        assert_eq!(part_two(&parse_input(TESTDATA2)?), 4);
+        assert_eq!(part_two(&parse_input(TESTDATA2)?, &Schema::default()), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_and_duplicate_keys() -> Result<()> {
+        let input = parse_input(
+            "byr:1937 iyr:2017 eyr:2020 hgt:183cm\nhcl:#fffffd ecl:gry pid:860033327 xyz:a-b\n\n\
+             byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd\necl:gry pid:860033327 byr:1940",
+        )?;
+        let mut schema = Schema::default();
+
+        assert_eq!(input.len(), 2);
+        assert_eq!(input[0].get(&Key::from("xyz")).unwrap().raw, "a-b");
+        assert_eq!(part_one(&input, &schema), 2);
+        assert_eq!(
+            input[0].validate(&schema),
+            vec![Violation::new(
+                0,
+                Some(2),
+                Key::Other(String::from("xyz")),
+                ViolationKind::UnknownKey
+            )]
+        );
+        assert_eq!(
+            input[1].validate(&schema),
+            vec![Violation::new(
+                1,
+                Some(5),
+                Key::Byr,
+                ViolationKind::DuplicateKey {
+                    first_line: Some(4)
+                }
+            )]
+        );
+
+        schema.set_mode(Mode::Lenient);
+        assert!(input[0].is_valid(&schema));
+        assert!(!input[1].is_valid(&schema));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_tokens() -> Result<()> {
+        let input = parse_input("byr:1937 foo\n\niyr:2017")?;
+        let schema = Schema::default();
+
+        assert_eq!(input.len(), 2);
+        assert_eq!(input[0].get(&Key::Byr).unwrap().raw, "1937");
+        assert_eq!(input[1].get(&Key::Iyr).unwrap().raw, "2017");
+        assert!(input[0].validate(&schema).contains(&Violation::new(
+            0,
+            Some(1),
+            Key::Other(String::from("foo")),
+            ViolationKind::Malformed
+        )));
+        assert!(parse_input("byr:1937\n\n\n\niyr:2017").is_err());
+        assert_eq!(parse_input("byr:1937\n\n")?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve() -> Result<()> {
+        let mut input = parse_input("byr:1937 hgt:183cm hcl:#fffffd ecl:gry\npid:86003332 xyz:a")?;
+        let schema = Schema::default();
+        input[0].resolve(&schema);
+
+        let value = |key| input[0].get(&key).unwrap().value.clone();
+
+        assert_eq!(value(Key::Byr), Some(Ok(Value::Year(1937))));
+        assert!(matches!(
+            value(Key::Hgt),
+            Some(Ok(Value::Measurement(measurement)))
+                if measurement.value == 183 && measurement.unit.as_str() == "cm"
+        ));
+        assert_eq!(value(Key::Ecl), Some(Ok(Value::Enum(String::from("gry")))));
+        assert_eq!(
+            value(Key::Pid),
+            Some(Err(ValueError::BadFormat(String::from("86003332"))))
+        );
+        assert_eq!(value(Key::from("xyz")), None);
+        assert!(input[0].validate(&schema).contains(&Violation::new(
+            0,
+            Some(2),
+            Key::Pid,
+            ViolationKind::BadFormat {
+                value: String::from("86003332")
+            }
+        )));
 
         Ok(())
     }
 
+    #[test]
+    fn test_line_of() {
+        let input = "byr:1937\n\niyr:2017 eyr:2020\r\nhgt:183cm";
+        let line_starts = line_starts(input);
+
+        assert_eq!(line_starts, vec![0, 9, 10, 29]);
+        assert_eq!(line_of(input, &line_starts, &input[0..]), 1);
+        assert_eq!(line_of(input, &line_starts, &input[19..]), 3);
+        assert_eq!(line_of(input, &line_starts, &input[29..]), 4);
+    }
+
     #[test]
     fn test_part_two() -> Result<()> {
-        assert_eq!(part_two(&parse_input(DATA)?), 194);
+        assert_eq!(part_two(&parse_input(DATA)?, &Schema::default()), 194);
 
         Ok(())
     }
@@ -0,0 +1,194 @@
+use crate::schema::Schema;
+use crate::{value::ValueError, Key, Passport};
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Violation {
+    pub passport: usize,
+    pub line: Option<usize>,
+    pub key: Key,
+    #[serde(flatten)]
+    pub kind: ViolationKind,
+}
+
+impl Violation {
+    pub fn new(passport: usize, line: Option<usize>, key: Key, kind: ViolationKind) -> Self {
+        Self {
+            passport,
+            line,
+            key,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "passport {}", self.passport)?;
+        if let Some(line) = self.line {
+            write!(f, " (line {line})")?;
+        }
+        write!(f, ": {} {}", self.key.as_str(), self.kind)
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "violation", rename_all = "snake_case")]
+pub enum ViolationKind {
+    Missing,
+    Unparseable { value: String },
+    OutOfRange { value: u16, min: u16, max: u16 },
+    BadUnit { unit: String },
+    BadFormat { value: String },
+    UnknownKey,
+    DuplicateKey { first_line: Option<usize> },
+    Malformed,
+}
+
+impl From<ValueError> for ViolationKind {
+    fn from(error: ValueError) -> Self {
+        match error {
+            ValueError::Unparseable(value) => ViolationKind::Unparseable { value },
+            ValueError::BadUnit(unit) => ViolationKind::BadUnit { unit },
+            ValueError::BadFormat(value) => ViolationKind::BadFormat { value },
+        }
+    }
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::Missing => write!(f, "is missing"),
+            ViolationKind::Unparseable { value } => write!(f, "cannot parse {value:?}"),
+            ViolationKind::OutOfRange { value, min, max } => {
+                write!(f, "{value} is out of range {min}..={max}")
+            }
+            ViolationKind::BadUnit { unit } => write!(f, "has bad unit {unit:?}"),
+            ViolationKind::BadFormat { value } => write!(f, "has bad format {value:?}"),
+            ViolationKind::UnknownKey => write!(f, "is not a known key"),
+            ViolationKind::Malformed => write!(f, "is not a key:value pair"),
+            ViolationKind::DuplicateKey {
+                first_line: Some(first_line),
+            } => write!(f, "is a duplicate of line {first_line}"),
+            ViolationKind::DuplicateKey { first_line: None } => write!(f, "is a duplicate"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    passports: usize,
+    valid: usize,
+    violations: Vec<Violation>,
+}
+
+impl Report {
+    pub fn new(input: &[Passport], schema: &Schema) -> Self {
+        let per_passport = input
+            .iter()
+            .map(|passport| passport.validate(schema))
+            .collect::<Vec<Vec<Violation>>>();
+
+        Self {
+            passports: input.len(),
+            valid: per_passport.iter().filter(|v| v.is_empty()).count(),
+            violations: per_passport.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = self
+            .violations
+            .iter()
+            .map(|violation| format!("{violation}\n"))
+            .collect::<String>();
+        text.push_str(&format!(
+            "{} of {} passports valid, {} violations\n",
+            self.valid,
+            self.passports,
+            self.violations.len()
+        ));
+
+        text
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const TESTDATA2: &str = include_str!("test2.txt");
+
+    #[test]
+    fn test_report_testdata() -> Result<()> {
+        let report = Report::new(&parse_input(TESTDATA2)?, &Schema::default());
+
+        assert_eq!(report.passports, 8);
+        assert_eq!(report.valid, 4);
+        assert_eq!(
+            report.violations[0],
+            Violation::new(
+                0,
+                Some(1),
+                Key::Eyr,
+                ViolationKind::OutOfRange {
+                    value: 1972,
+                    min: 2020,
+                    max: 2030
+                }
+            )
+        );
+        assert_eq!(
+            report.violations[1],
+            Violation::new(
+                0,
+                Some(2),
+                Key::Hgt,
+                ViolationKind::BadUnit {
+                    unit: String::new()
+                }
+            )
+        );
+        assert_eq!(
+            report.violations[2],
+            Violation::new(
+                0,
+                Some(2),
+                Key::Pid,
+                ViolationKind::BadFormat {
+                    value: String::from("186cm")
+                }
+            )
+        );
+        assert!(report
+            .violations
+            .iter()
+            .all(|violation| violation.passport < 4));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_formats() -> Result<()> {
+        let report = Report::new(&parse_input(TESTDATA2)?, &Schema::default());
+
+        let text = report.to_text();
+        assert!(text.starts_with("passport 0 (line 1): eyr 1972 is out of range 2020..=2030\n"));
+        assert!(text.ends_with("4 of 8 passports valid, 12 violations\n"));
+
+        let json = serde_json::from_str::<serde_json::Value>(&report.to_json()?)?;
+        assert_eq!(json["valid"], 4);
+        assert_eq!(json["violations"][1]["key"], "hgt");
+        assert_eq!(json["violations"][1]["violation"], "bad_unit");
+        assert_eq!(json["violations"][1]["line"], 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{
+    value::{Value, ValueError},
+    Field, Key, ViolationKind,
+};
+use anyhow::Result;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+const DEFAULT_SCHEMA: &str = include_str!("schema.json");
+
+#[derive(Debug, Deserialize)]
+pub struct Schema {
+    #[serde(default)]
+    unknown_keys: Mode,
+    fields: Vec<FieldRule>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FieldRule {
+    pub key: Key,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldKind {
+    Year { min: u16, max: u16 },
+    Measurement { units: Vec<UnitRange> },
+    HexColor,
+    Enum { values: Vec<String> },
+    Digits { length: usize },
+    Any,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnitRange {
+    unit: String,
+    min: u16,
+    max: u16,
+}
+
+impl Schema {
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn fields(&self) -> &[FieldRule] {
+        &self.fields
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.unknown_keys
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.unknown_keys = mode;
+    }
+
+    pub fn rule(&self, key: &Key) -> Option<&FieldRule> {
+        self.fields.iter().find(|rule| rule.key == *key)
+    }
+
+    pub fn declares(&self, key: &Key) -> bool {
+        self.rule(key).is_some()
+    }
+
+    pub fn required_keys(&self) -> impl Iterator<Item = &Key> {
+        self.fields
+            .iter()
+            .filter(|rule| rule.required)
+            .map(|rule| &rule.key)
+    }
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self::from_json(DEFAULT_SCHEMA).expect("Built-in schema should be valid")
+    }
+}
+
+impl FieldRule {
+    // Checks the value `Passport::resolve` read; a field it has not resolved
+    // is read here instead.
+    pub fn check(&self, field: &Field) -> std::result::Result<(), ViolationKind> {
+        match &field.value {
+            Some(value) => self.kind.check(value.as_ref().map_err(Clone::clone)?),
+            None => self.kind.check(&self.kind.parse(&field.raw)?),
+        }
+    }
+}
+
+impl FieldKind {
+    // Reads `raw` with the grammar of this kind, using the units and values the
+    // schema declares.
+    pub fn parse(&self, raw: &str) -> std::result::Result<Value, ValueError> {
+        match self {
+            FieldKind::Year { .. } => Value::year(raw),
+            FieldKind::Measurement { units } => {
+                let units = units
+                    .iter()
+                    .map(|range| range.unit.as_str())
+                    .collect::<Vec<&str>>();
+                Value::measurement(raw, &units)
+            }
+            FieldKind::HexColor => Value::hex_color(raw),
+            FieldKind::Enum { values } => {
+                let values = values.iter().map(String::as_str).collect::<Vec<&str>>();
+                Value::one_of(raw, &values)
+            }
+            FieldKind::Digits { length } => Value::digits(raw, *length),
+            FieldKind::Any => Ok(Value::text(raw)),
+        }
+    }
+
+    pub fn check(&self, value: &Value) -> std::result::Result<(), ViolationKind> {
+        match (self, value) {
+            (FieldKind::Year { min, max }, Value::Year(year)) => check_range(*year, *min, *max),
+            (FieldKind::Measurement { units }, Value::Measurement(measurement)) => {
+                let range = units
+                    .iter()
+                    .find(|range| range.unit == measurement.unit.as_str())
+                    .ok_or_else(|| ViolationKind::BadUnit {
+                        unit: measurement.unit.as_str().to_string(),
+                    })?;
+                check_range(measurement.value, range.min, range.max)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check_range(value: u16, min: u16, max: u16) -> std::result::Result<(), ViolationKind> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(ViolationKind::OutOfRange { value, min, max })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Measurement;
+
+    fn check(schema: &Schema, key: Key, raw: &str) -> std::result::Result<(), ViolationKind> {
+        let rule = schema.rule(&key).unwrap();
+        rule.kind.check(&rule.kind.parse(raw)?)
+    }
+
+    #[test]
+    fn test_default_schema() {
+        let schema = Schema::default();
+
+        assert!(check(&schema, Key::Byr, "2002").is_ok());
+        assert!(check(&schema, Key::Byr, "2003").is_err());
+        assert!(check(&schema, Key::Hgt, "60in").is_ok());
+        assert!(check(&schema, Key::Hgt, "190cm").is_ok());
+        assert!(check(&schema, Key::Hgt, "190in").is_err());
+        assert!(check(&schema, Key::Hgt, "190").is_err());
+        assert!(check(&schema, Key::Hgt, "m").is_err());
+        assert!(check(&schema, Key::Hcl, "#123abc").is_ok());
+        assert!(check(&schema, Key::Hcl, "#123abz").is_err());
+        assert!(check(&schema, Key::Hcl, "123abc").is_err());
+        assert!(check(&schema, Key::Hcl, "#1").is_err());
+        assert!(check(&schema, Key::Ecl, "brn").is_ok());
+        assert!(check(&schema, Key::Ecl, "wat").is_err());
+        assert!(check(&schema, Key::Pid, "000000001").is_ok());
+        assert!(check(&schema, Key::Pid, "0123456789").is_err());
+        assert_eq!(schema.required_keys().count(), 7);
+    }
+
+    #[test]
+    fn test_violation_kinds() {
+        let schema = Schema::default();
+
+        assert_eq!(
+            check(&schema, Key::Byr, "19x7"),
+            Err(ViolationKind::Unparseable {
+                value: String::from("19x7")
+            })
+        );
+        assert_eq!(
+            check(&schema, Key::Byr, "2003"),
+            Err(ViolationKind::OutOfRange {
+                value: 2003,
+                min: 1920,
+                max: 2002
+            })
+        );
+        assert_eq!(
+            check(&schema, Key::Hgt, "190"),
+            Err(ViolationKind::BadUnit {
+                unit: String::new()
+            })
+        );
+        assert_eq!(
+            check(&schema, Key::Hgt, "cm"),
+            Err(ViolationKind::Unparseable {
+                value: String::from("cm")
+            })
+        );
+        assert_eq!(
+            check(&schema, Key::Ecl, "wat"),
+            Err(ViolationKind::BadFormat {
+                value: String::from("wat")
+            })
+        );
+    }
+
+    #[test]
+    fn test_custom_schema() -> Result<()> {
+        let schema = Schema::from_json(
+            r#"{ "fields": [
+                { "key": "byr", "required": true, "type": "year", "min": 1900, "max": 1950 },
+                { "key": "cid", "required": true, "type": "digits", "length": 3 }
+            ] }"#,
+        )?;
+
+        assert!(check(&schema, Key::Byr, "1937").is_ok());
+        assert!(check(&schema, Key::Byr, "1990").is_err());
+        assert!(check(&schema, Key::Cid, "147").is_ok());
+        assert!(check(&schema, Key::Cid, "88").is_err());
+        assert_eq!(schema.required_keys().count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_drives_grammar() -> Result<()> {
+        let schema = Schema::from_json(
+            r#"{ "fields": [
+                { "key": "hgt", "type": "measurement", "units": [ { "unit": "mm", "min": 1000, "max": 2500 } ] },
+                { "key": "ecl", "type": "enum", "values": ["red"] },
+                { "key": "cid", "type": "year", "min": 1900, "max": 2000 },
+                { "key": "xyz", "type": "hex_color" },
+                { "key": "hcl", "type": "any" }
+            ] }"#,
+        )?;
+        let xyz = Key::Other(String::from("xyz"));
+
+        assert!(check(&schema, Key::Hgt, "1800mm").is_ok());
+        assert_eq!(
+            check(&schema, Key::Hgt, "180cm"),
+            Err(ViolationKind::BadUnit {
+                unit: String::from("cm")
+            })
+        );
+        assert!(check(&schema, Key::Hgt, "800mm").is_err());
+        assert!(check(&schema, Key::Ecl, "red").is_ok());
+        assert!(check(&schema, Key::Ecl, "brn").is_err());
+        assert!(check(&schema, Key::Cid, "1937").is_ok());
+        assert!(check(&schema, Key::Cid, "2020").is_err());
+        assert!(check(&schema, xyz.clone(), "#123abc").is_ok());
+        assert!(check(&schema, xyz, "a-b").is_err());
+        assert!(check(&schema, Key::Hcl, "a-b").is_ok());
+        assert!(matches!(
+            schema.fields()[0].kind.parse("1800mm"),
+            Ok(Value::Measurement(Measurement { value: 1800, unit })) if unit.as_str() == "mm"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        assert!(
+            Schema::from_json(r#"{ "fields": [ { "key": "byr", "type": "date" } ] }"#).is_err()
+        );
+        assert!(Schema::from_json(r#"{ "unknown_keys": "maybe", "fields": [] }"#).is_err());
+    }
+
+    #[test]
+    fn test_schema_other_key() -> Result<()> {
+        let schema = Schema::from_json(
+            r#"{ "unknown_keys": "lenient", "fields": [ { "key": "xyz", "type": "any" } ] }"#,
+        )?;
+
+        assert_eq!(schema.mode(), Mode::Lenient);
+        assert!(schema.declares(&Key::Other(String::from("xyz"))));
+        assert!(!schema.declares(&Key::Byr));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use nom::{
+    bytes::complete::take_while_m_n,
+    character::complete::{self, char},
+    combinator::{all_consuming, map, map_res, rest, verify},
+    error::Error,
+    multi::count,
+    sequence::preceded,
+    AsChar, IResult, Parser,
+};
+
+// A field value read with the grammar of its schema `FieldKind`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Year(u16),
+    Measurement(Measurement),
+    HexColor(HexColor),
+    Enum(String),
+    Digits(String),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub value: u16,
+    pub unit: Unit,
+}
+
+// One of the units a measurement field declares in the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unit(String);
+
+impl Unit {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HexColor(pub [u8; 3]);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueError {
+    Unparseable(String),
+    BadUnit(String),
+    BadFormat(String),
+}
+
+impl Value {
+    pub fn year(raw: &str) -> Result<Self, ValueError> {
+        all_consuming(parse_year)
+            .parse(raw)
+            .map(|(_, year)| Value::Year(year))
+            .map_err(|_| ValueError::Unparseable(raw.to_string()))
+    }
+
+    pub fn measurement(raw: &str, units: &[&str]) -> Result<Self, ValueError> {
+        let (unit, value) =
+            parse_amount(raw).map_err(|_| ValueError::Unparseable(raw.to_string()))?;
+        let (_, unit) = all_consuming(parse_one_of(units))
+            .parse(unit)
+            .map_err(|_| ValueError::BadUnit(unit.to_string()))?;
+
+        Ok(Value::Measurement(Measurement {
+            value,
+            unit: Unit(unit.to_string()),
+        }))
+    }
+
+    pub fn hex_color(raw: &str) -> Result<Self, ValueError> {
+        all_consuming(parse_hex_color)
+            .parse(raw)
+            .map(|(_, color)| Value::HexColor(color))
+            .map_err(|_| ValueError::BadFormat(raw.to_string()))
+    }
+
+    pub fn one_of(raw: &str, values: &[&str]) -> Result<Self, ValueError> {
+        all_consuming(parse_one_of(values))
+            .parse(raw)
+            .map(|(_, value)| Value::Enum(value.to_string()))
+            .map_err(|_| ValueError::BadFormat(raw.to_string()))
+    }
+
+    pub fn digits(raw: &str, length: usize) -> Result<Self, ValueError> {
+        all_consuming(parse_digits(length))
+            .parse(raw)
+            .map(|(_, digits)| Value::Digits(digits.to_string()))
+            .map_err(|_| ValueError::BadFormat(raw.to_string()))
+    }
+
+    pub fn text(raw: &str) -> Self {
+        Value::Text(raw.to_string())
+    }
+}
+
+fn parse_amount(input: &str) -> IResult<&str, u16> {
+    complete::u16(input)
+}
+
+fn parse_digits<'a>(
+    length: usize,
+) -> impl Parser<&'a str, Output = &'a str, Error = Error<&'a str>> {
+    take_while_m_n(length, length, AsChar::is_dec_digit)
+}
+
+fn parse_year(input: &str) -> IResult<&str, u16> {
+    map_res(take_while_m_n(4, 4, AsChar::is_dec_digit), str::parse).parse(input)
+}
+
+// The whole remaining input, if it is one of `names`.
+fn parse_one_of<'a>(
+    names: &[&str],
+) -> impl Parser<&'a str, Output = &'a str, Error = Error<&'a str>> {
+    verify(rest, move |input: &str| names.contains(&input))
+}
+
+fn parse_hex_color(input: &str) -> IResult<&str, HexColor> {
+    map(
+        preceded(char('#'), count(parse_hex_byte, 3)),
+        |bytes: Vec<u8>| HexColor([bytes[0], bytes[1], bytes[2]]),
+    )
+    .parse(input)
+}
+
+fn parse_hex_byte(input: &str) -> IResult<&str, u8> {
+    map_res(take_while_m_n(2, 2, AsChar::is_hex_digit), |hex| {
+        u8::from_str_radix(hex, 16)
+    })
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNITS: [&str; 2] = ["cm", "in"];
+    const EYE_COLORS: [&str; 3] = ["amb", "blu", "brn"];
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(Value::year("2002"), Ok(Value::Year(2002)));
+        assert_eq!(
+            Value::measurement("60in", &UNITS),
+            Ok(Value::Measurement(Measurement {
+                value: 60,
+                unit: Unit(String::from("in"))
+            }))
+        );
+        assert_eq!(
+            Value::hex_color("#123abc"),
+            Ok(Value::HexColor(HexColor([0x12, 0x3a, 0xbc])))
+        );
+        assert_eq!(
+            Value::one_of("brn", &EYE_COLORS),
+            Ok(Value::Enum(String::from("brn")))
+        );
+        assert_eq!(
+            Value::digits("000000001", 9),
+            Ok(Value::Digits(String::from("000000001")))
+        );
+        assert_eq!(Value::text("a-b"), Value::Text(String::from("a-b")));
+    }
+
+    #[test]
+    fn test_parse_invalid_values() {
+        let unparseable = |raw: &str| Err(ValueError::Unparseable(raw.to_string()));
+        let bad_format = |raw: &str| Err(ValueError::BadFormat(raw.to_string()));
+
+        assert_eq!(Value::year("19x7"), unparseable("19x7"));
+        assert_eq!(Value::year("02002"), unparseable("02002"));
+        assert_eq!(Value::measurement("", &UNITS), unparseable(""));
+        assert_eq!(Value::measurement("m", &UNITS), unparseable("m"));
+        assert_eq!(Value::measurement("é", &UNITS), unparseable("é"));
+        assert_eq!(
+            Value::measurement("190é", &UNITS),
+            Err(ValueError::BadUnit(String::from("é")))
+        );
+        assert_eq!(
+            Value::measurement("190", &UNITS),
+            Err(ValueError::BadUnit(String::new()))
+        );
+        assert_eq!(
+            Value::measurement("190cmm", &UNITS),
+            Err(ValueError::BadUnit(String::from("cmm")))
+        );
+        assert_eq!(Value::hex_color("#1"), bad_format("#1"));
+        assert_eq!(Value::hex_color("#12345678"), bad_format("#12345678"));
+        assert_eq!(Value::hex_color("#123abz"), bad_format("#123abz"));
+        assert_eq!(Value::hex_color("123abc"), bad_format("123abc"));
+        assert_eq!(Value::one_of("wat", &EYE_COLORS), bad_format("wat"));
+        assert_eq!(Value::digits("186cm", 9), bad_format("186cm"));
+        assert_eq!(Value::digits("0123456789", 9), bad_format("0123456789"));
+    }
+}
@@ -1,16 +1,29 @@
-use anyhow::Result;
-use bit_vec::BitVec;
+use anyhow::{Error, Result};
 use nom::{
-    character::{complete::line_ending, complete::one_of},
-    combinator::map,
-    multi::{many1, separated_list1},
+    bytes::complete::take_while1, character::complete::line_ending, multi::separated_list1,
     IResult, Parser,
 };
+use seatmap::{check_duplicates, SeatMap};
+use std::{env, fmt};
+
+mod seatmap;
 
 const DATA: &str = include_str!("input.txt");
 
 fn main() -> Result<()> {
-    let (took, result) = took::took(|| parse_input(DATA));
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let layout = match (
+        option(&args, "--row-bits")?,
+        option(&args, "--column-bits")?,
+    ) {
+        (None, None) => Layout::default(),
+        (row_bits, column_bits) => Layout::new(
+            row_bits.unwrap_or(Layout::default().row_bits),
+            column_bits.unwrap_or(Layout::default().column_bits),
+        )?,
+    };
+
+    let (took, result) = took::took(|| parse_input(DATA, layout));
     println!("Time spent parsing: {}", took);
     let input = result?;
 
@@ -18,50 +31,214 @@ fn main() -> Result<()> {
     println!("Result part one: {result}");
     println!("Time spent: {took}");
 
-    let (took, result) = took::took(|| part_two(&input));
-    println!("Result part two: {result}");
-    println!("Time spent: {took}");
+    let (took, result) = took::took(|| part_two(&input, layout));
+    if let Ok(seat) = &result {
+        println!("Result part two: {seat}");
+        println!("Time spent: {took}");
+    }
+
+    // The map helps most when part two fails, so print it, and handle `--encode`,
+    // before the error.
+    if args.iter().any(|arg| arg == "--map") {
+        let map = SeatMap::new(&input, layout);
+        print!("{}", map.render(result.as_ref().ok().copied()));
+        print!("{}", map.report());
+    }
+
+    if let Some(id) = option(&args, "--encode")? {
+        let pass = BoardingPass::from_id(id, layout)?;
+        println!(
+            "Seat {id}: {} (row {}, column {})",
+            pass.encode(layout),
+            pass.row,
+            pass.column
+        );
+    }
+    result?;
 
     Ok(())
 }
 
-fn part_one(input: &[u16]) -> u16 {
-    *input.iter().max().unwrap()
+fn option(args: &[String], name: &str) -> Result<Option<u32>> {
+    args.iter()
+        .skip_while(|arg| *arg != name)
+        .nth(1)
+        .map(|value| Ok(value.parse::<u32>()?))
+        .transpose()
 }
 
-fn part_two(input: &[u16]) -> u16 {
-    let mut bv: BitVec = BitVec::from_elem(1024, false);
-    for seat_id in input {
-        bv.set(*seat_id as usize, true);
+fn part_one(input: &[BoardingPass]) -> u32 {
+    input.iter().map(|pass| pass.id).max().unwrap()
+}
+
+fn part_two(input: &[BoardingPass], layout: Layout) -> Result<u32> {
+    check_duplicates(input, layout)?;
+
+    Ok(SeatMap::new(input, layout).free_seat()?)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Layout {
+    row_bits: u32,
+    column_bits: u32,
+}
+
+impl Layout {
+    pub fn new(row_bits: u32, column_bits: u32) -> Result<Self> {
+        if row_bits == 0 || column_bits == 0 || row_bits + column_bits > 24 {
+            return Err(Error::msg(format!(
+                "Unsupported layout of {row_bits} row bits and {column_bits} column bits"
+            )));
+        }
+
+        Ok(Self {
+            row_bits,
+            column_bits,
+        })
     }
 
-    bv.iter()
-        .enumerate()
-        .skip_while(|(_, b)| b == &false)
-        .find(|(_, b)| b == &false)
-        .map(|(idx, _)| idx)
-        .unwrap() as u16
+    pub fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    pub fn columns(&self) -> u32 {
+        1 << self.column_bits
+    }
+
+    pub fn seats(&self) -> usize {
+        (self.rows() * self.columns()) as usize
+    }
+
+    pub fn code_length(&self) -> usize {
+        (self.row_bits + self.column_bits) as usize
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            row_bits: 7,
+            column_bits: 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BoardingPass {
+    row: u32,
+    column: u32,
+    id: u32,
+}
+
+impl BoardingPass {
+    pub fn new(row: u32, column: u32, layout: Layout) -> Result<Self> {
+        if row >= layout.rows() || column >= layout.columns() {
+            return Err(Error::msg(format!(
+                "Seat at row {row}, column {column} is outside the plane"
+            )));
+        }
+
+        Ok(Self {
+            row,
+            column,
+            id: row * layout.columns() + column,
+        })
+    }
+
+    pub fn from_id(id: u32, layout: Layout) -> Result<Self> {
+        Self::new(id / layout.columns(), id % layout.columns(), layout)
+    }
+
+    pub fn decode(code: &str, layout: Layout) -> std::result::Result<Self, DecodeError> {
+        let length = code.chars().count();
+        if length != layout.code_length() {
+            return Err(DecodeError::Length {
+                expected: layout.code_length(),
+                actual: length,
+            });
+        }
+
+        let (row, column) = code.chars().enumerate().try_fold(
+            (0, 0),
+            |(row, column), (position, letter)| match (position < layout.row_bits as usize, letter)
+            {
+                (true, 'F') => Ok((row << 1, column)),
+                (true, 'B') => Ok((row << 1 | 1, column)),
+                (false, 'L') => Ok((row, column << 1)),
+                (false, 'R') => Ok((row, column << 1 | 1)),
+                _ => Err(DecodeError::Letter { position, letter }),
+            },
+        )?;
+
+        Ok(Self {
+            row,
+            column,
+            id: row * layout.columns() + column,
+        })
+    }
+
+    pub fn encode(&self, layout: Layout) -> String {
+        let row = (0..layout.row_bits)
+            .rev()
+            .map(|bit| if self.row >> bit & 1 == 1 { 'B' } else { 'F' });
+        let column = (0..layout.column_bits).rev().map(|bit| {
+            if self.column >> bit & 1 == 1 {
+                'R'
+            } else {
+                'L'
+            }
+        });
+
+        row.chain(column).collect()
+    }
 }
 
-fn parse(input: &str) -> IResult<&str, Vec<u16>> {
+#[derive(Debug, PartialEq)]
+enum DecodeError {
+    Length { expected: usize, actual: usize },
+    Letter { position: usize, letter: char },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Length { expected, actual } => {
+                write!(f, "expected {expected} letters, got {actual}")
+            }
+            DecodeError::Letter { position, letter } => {
+                write!(f, "unexpected letter {letter:?} at position {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn parse(input: &str) -> IResult<&str, Vec<&str>> {
     separated_list1(line_ending, parse_line).parse(input)
 }
 
-fn parse_line(input: &str) -> IResult<&str, u16> {
-    map(many1(one_of("FBLR")), |v: Vec<char>| {
-        let bin_string = v
-            .into_iter()
-            .map(|c| if c == 'L' || c == 'F' { '0' } else { '1' })
-            .collect::<String>();
-        u16::from_str_radix(&bin_string, 2).unwrap()
-    })
-    .parse(input)
+fn parse_line(input: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c != '\r' && c != '\n')(input)
 }
 
-fn parse_input(input: &'static str) -> Result<Vec<u16>> {
-    let (_, input) = parse(input)?;
+fn parse_input(input: &'static str, layout: Layout) -> Result<Vec<BoardingPass>> {
+    let (rest, lines) = parse(input)?;
+    let rest = rest.trim_start();
+    if !rest.is_empty() {
+        let line = input[..input.len() - rest.len()].matches('\n').count() + 1;
+        let text = rest.lines().next().unwrap_or_default();
+        return Err(Error::msg(format!("Line {line}: cannot parse {text:?}")));
+    }
 
-    Ok(input)
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            BoardingPass::decode(line, layout)
+                .map_err(|e| Error::msg(format!("Line {}: {e}", idx + 1)))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -72,21 +249,101 @@ mod tests {
 
     #[test]
     fn test_part_one_testdata() -> Result<()> {
-        assert_eq!(part_one(&parse_input(TESTDATA)?), 820);
+        assert_eq!(part_one(&parse_input(TESTDATA, Layout::default())?), 820);
 
         Ok(())
     }
 
     #[test]
     fn test_part_one() -> Result<()> {
-        assert_eq!(part_one(&parse_input(DATA)?), 919);
+        assert_eq!(part_one(&parse_input(DATA, Layout::default())?), 919);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two() -> Result<()> {
-        assert_eq!(part_two(&parse_input(DATA)?), 642);
+        assert_eq!(
+            part_two(&parse_input(DATA, Layout::default())?, Layout::default())?,
+            642
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_testdata() -> Result<()> {
+        let layout = Layout::default();
+        let passes = parse_input(TESTDATA, Layout::default())?;
+
+        assert_eq!(
+            passes,
+            vec![
+                BoardingPass::new(44, 5, layout)?,
+                BoardingPass::new(70, 7, layout)?,
+                BoardingPass::new(14, 7, layout)?,
+                BoardingPass::new(102, 4, layout)?,
+            ]
+        );
+        assert_eq!(
+            passes.iter().map(|pass| pass.id).collect::<Vec<u32>>(),
+            vec![357, 567, 119, 820]
+        );
+        assert!(TESTDATA
+            .lines()
+            .zip(&passes)
+            .all(|(line, pass)| pass.encode(layout) == line));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_invalid() -> Result<()> {
+        let layout = Layout::default();
+
+        assert_eq!(
+            BoardingPass::decode("FBFBBFFRL", layout),
+            Err(DecodeError::Length {
+                expected: 10,
+                actual: 9
+            })
+        );
+        assert_eq!(
+            BoardingPass::decode("FBFBBFRRLR", layout),
+            Err(DecodeError::Letter {
+                position: 6,
+                letter: 'R'
+            })
+        );
+        assert_eq!(
+            BoardingPass::decode("FBFBBFFRLX", layout),
+            Err(DecodeError::Letter {
+                position: 9,
+                letter: 'X'
+            })
+        );
+        assert!(parse_input("FBFBBFFRLR\nFBFBBFFRL", layout).is_err());
+        assert_eq!(
+            parse_input("FBFBBFFRLR\n\nFBFBBFFRLX", layout)
+                .err()
+                .map(|e| e.to_string()),
+            Some(String::from("Line 3: cannot parse \"FBFBBFFRLX\""))
+        );
+        assert_eq!(parse_input("FBFBBFFRLR\n\n", layout)?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_layout() -> Result<()> {
+        let layout = Layout::new(9, 4)?;
+        let pass = BoardingPass::from_id(5000, layout)?;
+
+        assert_eq!((pass.row, pass.column), (312, 8));
+        assert_eq!(pass.encode(layout), "BFFBBBFFFRLLL");
+        assert_eq!(BoardingPass::decode("BFFBBBFFFRLLL", layout), Ok(pass));
+        assert!(BoardingPass::from_id(layout.seats() as u32, layout).is_err());
+        assert!(Layout::new(0, 3).is_err());
 
         Ok(())
     }
@@ -0,0 +1,319 @@
+use crate::{BoardingPass, Layout};
+use bit_vec::BitVec;
+use std::{fmt, ops::RangeInclusive};
+
+pub struct SeatMap {
+    layout: Layout,
+    occupied: BitVec,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RowStats {
+    pub row: u32,
+    pub occupied: u32,
+    pub empty: u32,
+}
+
+// A pass for a seat that an earlier pass, at index `first`, already holds.
+#[derive(Debug, PartialEq)]
+pub struct Duplicate {
+    pub id: u32,
+    pub first: usize,
+    pub second: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SeatError {
+    DuplicatePasses { duplicates: Vec<Duplicate> },
+    AmbiguousSeat { candidates: Vec<u32> },
+    NoFreeSeat,
+}
+
+impl fmt::Display for SeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeatError::DuplicatePasses { duplicates } => {
+                let duplicates = duplicates
+                    .iter()
+                    .map(|Duplicate { id, first, second }| {
+                        format!(
+                            "seat {id} appears on line {} and line {}",
+                            first + 1,
+                            second + 1
+                        )
+                    })
+                    .collect::<Vec<String>>();
+                write!(f, "{}", duplicates.join(", "))
+            }
+            SeatError::AmbiguousSeat { candidates } => {
+                write!(f, "multiple free seats qualify: {candidates:?}")
+            }
+            SeatError::NoFreeSeat => write!(f, "no free seat has occupied neighbours"),
+        }
+    }
+}
+
+impl std::error::Error for SeatError {}
+
+pub fn check_duplicates(input: &[BoardingPass], layout: Layout) -> Result<(), SeatError> {
+    let mut seen = vec![None; layout.seats()];
+    let mut duplicates = vec![];
+    for (idx, pass) in input.iter().enumerate() {
+        match seen[pass.id as usize] {
+            Some(first) => duplicates.push(Duplicate {
+                id: pass.id,
+                first,
+                second: idx,
+            }),
+            None => seen[pass.id as usize] = Some(idx),
+        }
+    }
+
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(SeatError::DuplicatePasses { duplicates })
+    }
+}
+
+impl SeatMap {
+    // Duplicate passes just mark their seat once; `check_duplicates` reports them.
+    pub fn new(input: &[BoardingPass], layout: Layout) -> Self {
+        let mut occupied = BitVec::from_elem(layout.seats(), false);
+        for pass in input {
+            occupied.set(pass.id as usize, true);
+        }
+
+        Self { layout, occupied }
+    }
+
+    pub fn free_seat(&self) -> Result<u32, SeatError> {
+        let candidates = (1..self.layout.seats().saturating_sub(1) as u32)
+            .filter(|id| {
+                !self.is_occupied(*id) && self.is_occupied(id - 1) && self.is_occupied(id + 1)
+            })
+            .collect::<Vec<u32>>();
+
+        match candidates.as_slice() {
+            [] => Err(SeatError::NoFreeSeat),
+            [id] => Ok(*id),
+            _ => Err(SeatError::AmbiguousSeat { candidates }),
+        }
+    }
+
+    pub fn is_occupied(&self, id: u32) -> bool {
+        self.occupied[id as usize]
+    }
+
+    pub fn gaps(&self) -> Vec<RangeInclusive<u32>> {
+        let ids = (0..self.layout.seats() as u32).collect::<Vec<u32>>();
+        let Some(first) = ids.iter().position(|id| self.is_occupied(*id)) else {
+            return vec![];
+        };
+        let last = ids.iter().rposition(|id| self.is_occupied(*id)).unwrap();
+
+        ids[first..=last]
+            .chunk_by(|a, b| self.is_occupied(*a) == self.is_occupied(*b))
+            .filter(|run| !self.is_occupied(run[0]))
+            .map(|run| run[0]..=run[run.len() - 1])
+            .collect()
+    }
+
+    pub fn row_stats(&self) -> Vec<RowStats> {
+        (0..self.layout.rows())
+            .map(|row| {
+                let occupied = (0..self.layout.columns())
+                    .filter(|column| self.is_occupied(row * self.layout.columns() + column))
+                    .count() as u32;
+                RowStats {
+                    row,
+                    occupied,
+                    empty: self.layout.columns() - occupied,
+                }
+            })
+            .collect()
+    }
+
+    pub fn render(&self, highlight: Option<u32>) -> String {
+        let width = self.layout.rows().to_string().len();
+        (0..self.layout.rows())
+            .map(|row| {
+                let seats = (0..self.layout.columns())
+                    .map(|column| {
+                        let id = row * self.layout.columns() + column;
+                        if highlight == Some(id) {
+                            '*'
+                        } else if self.is_occupied(id) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>();
+                format!("{row:>width$} {seats}\n")
+            })
+            .collect()
+    }
+
+    pub fn report(&self) -> String {
+        let stats = self.row_stats();
+        let mut report = String::from("row occupied empty\n");
+        for s in stats.iter().filter(|s| s.occupied > 0) {
+            report.push_str(&format!("{:>3} {:>8} {:>5}\n", s.row, s.occupied, s.empty));
+        }
+
+        let occupied = stats.iter().map(|s| s.occupied).sum::<u32>();
+        let full = stats.iter().filter(|s| s.empty == 0).count();
+        let partial = stats
+            .iter()
+            .filter(|s| s.occupied > 0 && s.empty > 0)
+            .count();
+        report.push_str(&format!(
+            "{occupied} of {} seats occupied, {full} full rows, {partial} partial rows\n",
+            self.layout.seats()
+        ));
+        for gap in self.gaps() {
+            if gap.start() == gap.end() {
+                report.push_str(&format!("Gap at seat {}\n", gap.start()));
+            } else {
+                report.push_str(&format!("Gap at seats {}..={}\n", gap.start(), gap.end()));
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+    use anyhow::Result;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_gaps() -> Result<()> {
+        let layout = Layout::new(2, 2)?;
+        let input = [1, 2, 5, 9, 10]
+            .iter()
+            .map(|id| BoardingPass::from_id(*id, layout))
+            .collect::<Result<Vec<BoardingPass>>>()?;
+        let map = SeatMap::new(&input, layout);
+
+        assert_eq!(map.gaps(), vec![3..=4, 6..=8]);
+        assert_eq!(
+            map.row_stats(),
+            vec![
+                RowStats {
+                    row: 0,
+                    occupied: 2,
+                    empty: 2
+                },
+                RowStats {
+                    row: 1,
+                    occupied: 1,
+                    empty: 3
+                },
+                RowStats {
+                    row: 2,
+                    occupied: 2,
+                    empty: 2
+                },
+                RowStats {
+                    row: 3,
+                    occupied: 0,
+                    empty: 4
+                },
+            ]
+        );
+        assert_eq!(map.render(Some(3)), "0 .##*\n1 .#..\n2 .##.\n3 ....\n");
+        assert!(SeatMap::new(&[], layout).gaps().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_testdata() -> Result<()> {
+        let layout = Layout::default();
+        let map = SeatMap::new(&parse_input(TESTDATA, layout)?, layout);
+        let rendered = map.render(None);
+        let lines = rendered.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 128);
+        assert_eq!(lines[44], " 44 .....#..");
+        assert_eq!(lines[102], "102 ....#...");
+        assert!(map
+            .report()
+            .ends_with("Gap at seats 358..=566\nGap at seats 568..=819\n"));
+
+        Ok(())
+    }
+
+    fn passes(ids: &[u32], layout: Layout) -> Result<Vec<BoardingPass>> {
+        ids.iter()
+            .map(|id| BoardingPass::from_id(*id, layout))
+            .collect()
+    }
+
+    #[test]
+    fn test_free_seat() -> Result<()> {
+        let layout = Layout::new(2, 2)?;
+
+        assert_eq!(
+            SeatMap::new(&passes(&[4, 5, 7, 8], layout)?, layout).free_seat(),
+            Ok(6)
+        );
+        assert_eq!(
+            SeatMap::new(&passes(&[4, 6, 8], layout)?, layout).free_seat(),
+            Err(SeatError::AmbiguousSeat {
+                candidates: vec![5, 7]
+            })
+        );
+        assert_eq!(
+            SeatMap::new(&passes(&[4, 5, 8, 9], layout)?, layout).free_seat(),
+            Err(SeatError::NoFreeSeat)
+        );
+        assert_eq!(
+            SeatMap::new(&[], layout).free_seat(),
+            Err(SeatError::NoFreeSeat)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_pass() -> Result<()> {
+        let layout = Layout::new(2, 2)?;
+
+        let input = passes(&[4, 5, 7, 5], layout)?;
+
+        assert_eq!(
+            check_duplicates(&input, layout),
+            Err(SeatError::DuplicatePasses {
+                duplicates: vec![Duplicate {
+                    id: 5,
+                    first: 1,
+                    second: 3
+                }]
+            })
+        );
+        assert_eq!(
+            check_duplicates(&passes(&[4, 5, 4, 7, 5, 4], layout)?, layout)
+                .err()
+                .map(|e| e.to_string()),
+            Some(String::from(
+                "seat 4 appears on line 1 and line 3, \
+                 seat 5 appears on line 2 and line 5, \
+                 seat 4 appears on line 1 and line 6"
+            ))
+        );
+        assert_eq!(check_duplicates(&input[..3], layout), Ok(()));
+        assert_eq!(
+            SeatMap::new(&input, layout).render(None),
+            "0 ....\n1 ##.#\n2 ....\n3 ....\n"
+        );
+
+        Ok(())
+    }
+}
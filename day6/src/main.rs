@@ -1,12 +1,12 @@
-use anyhow::Result;
-use itertools::Itertools;
+use anyhow::{Error, Result};
 use nom::{
-    character::complete::{alpha1, line_ending},
-    combinator::map,
-    multi::separated_list1,
-    sequence::pair,
-    IResult, Parser,
+    bytes::complete::take_while1, character::complete::line_ending, multi::separated_list1,
+    sequence::pair, IResult, Parser,
 };
+use report::Report;
+use std::env;
+
+mod report;
 
 const DATA: &str = include_str!("input.txt");
 
@@ -23,54 +23,146 @@ fn main() -> Result<()> {
     println!("Result part two: {result}");
     println!("Time spent: {took}");
 
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if let Some(k) = args.iter().skip_while(|arg| *arg != "--at-least").nth(1) {
+        let k = k.parse::<usize>()?;
+        let result = count_questions(&input, |group| group.answered_by_at_least(k));
+        println!("Answered by at least {k}: {result}");
+    }
+    if args.iter().any(|arg| arg == "--exactly-one") {
+        let result = count_questions(&input, Group::answered_by_exactly_one);
+        println!("Answered by exactly one: {result}");
+    }
+    if args.iter().any(|arg| arg == "--symmetric-difference") {
+        let result = count_questions(&input, Group::symmetric_difference);
+        println!("Symmetric difference: {result}");
+    }
+
+    if let Some(format) = args.iter().skip_while(|arg| *arg != "--report").nth(1) {
+        let report = Report::new(&input);
+        match format.as_str() {
+            "text" => print!("{}", report.to_text()),
+            "csv" => print!("{}", report.to_csv()),
+            _ => return Err(Error::msg(format!("Unknown report format {format}"))),
+        }
+    }
+
     Ok(())
 }
 
-fn part_one(input: &[Vec<Vec<char>>]) -> usize {
-    input
-        .iter()
-        .map(|x| x.iter().flatten().unique().count())
-        .sum()
+fn part_one(input: &[Group]) -> usize {
+    count_questions(input, Group::union)
+}
+
+fn part_two(input: &[Group]) -> usize {
+    count_questions(input, Group::intersection)
 }
 
-fn part_two(input: &[Vec<Vec<char>>]) -> usize {
+fn count_questions(input: &[Group], query: impl Fn(&Group) -> u32) -> usize {
     input
         .iter()
-        .map(|group| {
-            count_letters_per_groupline(group)
-                .iter()
-                .filter(|c| **c == group.len())
-                .count()
-        })
+        .map(|group| query(group).count_ones() as usize)
         .sum()
 }
 
-fn count_letters_per_groupline(group: &[Vec<char>]) -> [usize; 26] {
-    let mut arr: [usize; 26] = [0; 26];
-    group.iter().flatten().for_each(|c| {
-        arr[((*c as u8) - b'a') as usize] += 1;
-    });
+const ALL_QUESTIONS: u32 = (1 << 26) - 1;
+
+fn questions(mask: u32) -> String {
+    (0..26)
+        .filter(|bit| mask & 1 << bit != 0)
+        .map(|bit| (b'a' + bit) as char)
+        .collect()
+}
 
-    arr
+#[derive(Clone, Debug, PartialEq)]
+struct Group {
+    people: Vec<u32>,
 }
 
-fn parse(input: &str) -> IResult<&str, Vec<Vec<Vec<char>>>> {
+impl Group {
+    pub fn try_new(lines: &[&str]) -> Result<Self> {
+        let people = lines
+            .iter()
+            .enumerate()
+            .map(|(person, line)| {
+                line.chars().try_fold(0u32, |answers, c| {
+                    if c.is_ascii_lowercase() {
+                        Ok(answers | 1 << (c as u8 - b'a'))
+                    } else {
+                        Err(Error::msg(format!(
+                            "Person {person} answered {c:?}, which is not a question"
+                        )))
+                    }
+                })
+            })
+            .collect::<Result<Vec<u32>>>()?;
+
+        Ok(Self { people })
+    }
+
+    pub fn size(&self) -> usize {
+        self.people.len()
+    }
+
+    pub fn union(&self) -> u32 {
+        self.people.iter().fold(0, |acc, person| acc | person)
+    }
+
+    pub fn intersection(&self) -> u32 {
+        self.people
+            .iter()
+            .fold(ALL_QUESTIONS, |acc, person| acc & person)
+    }
+
+    // Questions answered by an odd number of people.
+    pub fn symmetric_difference(&self) -> u32 {
+        self.people.iter().fold(0, |acc, person| acc ^ person)
+    }
+
+    pub fn answered_by_at_least(&self, k: usize) -> u32 {
+        (0..26)
+            .map(|bit| 1 << bit)
+            .filter(|question| self.count(*question) >= k)
+            .fold(0, |acc, question| acc | question)
+    }
+
+    pub fn answered_by_exactly_one(&self) -> u32 {
+        self.answered_by_at_least(1) & !self.answered_by_at_least(2)
+    }
+
+    pub fn count(&self, question: u32) -> usize {
+        self.people
+            .iter()
+            .filter(|person| *person & question != 0)
+            .count()
+    }
+}
+
+fn parse(input: &str) -> IResult<&str, Vec<Vec<&str>>> {
     separated_list1(pair(line_ending, line_ending), parse_group).parse(input)
 }
 
-fn parse_group(input: &str) -> IResult<&str, Vec<Vec<char>>> {
-    map(separated_list1(line_ending, alpha1), |v| {
-        v.iter()
-            .map(|x: &&str| x.chars().collect::<Vec<char>>())
-            .collect::<Vec<Vec<char>>>()
-    })
-    .parse(input)
+fn parse_group(input: &str) -> IResult<&str, Vec<&str>> {
+    separated_list1(line_ending, parse_person).parse(input)
+}
+
+fn parse_person(input: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c != '\r' && c != '\n')(input)
 }
 
-fn parse_input(input: &'static str) -> Result<Vec<Vec<Vec<char>>>> {
-    let (_, input) = parse(input)?;
+fn parse_input(input: &'static str) -> Result<Vec<Group>> {
+    let (rest, groups) = parse(input)?;
+    if !rest.trim().is_empty() {
+        return Err(Error::msg(format!("Unexpected input: {rest:?}")));
+    }
 
-    Ok(input)
+    groups
+        .iter()
+        .enumerate()
+        .map(|(idx, lines)| {
+            Group::try_new(lines).map_err(|e| Error::msg(format!("Group {idx}: {e}")))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -106,4 +198,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_queries() -> Result<()> {
+        let group = Group::try_new(&["abc", "abd", "ae"])?;
+
+        assert_eq!(questions(group.union()), "abcde");
+        assert_eq!(questions(group.intersection()), "a");
+        assert_eq!(questions(group.symmetric_difference()), "acde");
+        assert_eq!(questions(group.answered_by_at_least(2)), "ab");
+        assert_eq!(questions(group.answered_by_at_least(3)), "a");
+        assert_eq!(questions(group.answered_by_exactly_one()), "cde");
+        assert_eq!(group.size(), 3);
+        assert_eq!(group.count(1 << 1), 2);
+        assert_eq!(count_questions(&[group], Group::answered_by_exactly_one), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_invalid_answers() {
+        assert!(Group::try_new(&["abC"]).is_err());
+        assert!(parse_input("abc\n\nab1").is_err());
+        assert!(parse_input("abc\n\nab\n\n\n\nc").is_err());
+    }
 }
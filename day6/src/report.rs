@@ -0,0 +1,202 @@
+use crate::{questions, Group};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq)]
+pub struct QuestionStats {
+    pub question: char,
+    pub groups: usize,
+    pub people: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Consensus {
+    pub group: usize,
+    pub size: usize,
+    pub answers: u32,
+}
+
+#[derive(Debug)]
+pub struct Report {
+    groups: usize,
+    questions: Vec<QuestionStats>,
+    group_sizes: BTreeMap<usize, usize>,
+    consensus: Vec<Consensus>,
+}
+
+impl Report {
+    pub fn new(input: &[Group]) -> Self {
+        let questions = (0..26)
+            .map(|bit| QuestionStats {
+                question: (b'a' + bit) as char,
+                groups: input
+                    .iter()
+                    .filter(|group| group.count(1 << bit) > 0)
+                    .count(),
+                people: input.iter().map(|group| group.count(1 << bit)).sum(),
+            })
+            .collect();
+
+        let mut group_sizes = BTreeMap::new();
+        for group in input {
+            *group_sizes.entry(group.size()).or_insert(0) += 1;
+        }
+
+        // Everybody in the group gave exactly the same answers.
+        let consensus = input
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| group.union() == group.intersection())
+            .map(|(idx, group)| Consensus {
+                group: idx,
+                size: group.size(),
+                answers: group.union(),
+            })
+            .collect();
+
+        Self {
+            groups: input.len(),
+            questions,
+            group_sizes,
+            consensus,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("question groups people\n");
+        for q in &self.questions {
+            text.push_str(&format!(
+                "{:>8} {:>6} {:>6}\n",
+                q.question, q.groups, q.people
+            ));
+        }
+
+        text.push_str("\nsize groups\n");
+        for (size, groups) in &self.group_sizes {
+            text.push_str(&format!("{size:>4} {groups:>6}\n"));
+        }
+
+        text.push_str(&format!(
+            "\n{} of {} groups in full consensus\n",
+            self.consensus.len(),
+            self.groups
+        ));
+        for c in &self.consensus {
+            text.push_str(&format!(
+                "Group {} ({} people): {}\n",
+                c.group,
+                c.size,
+                questions(c.answers)
+            ));
+        }
+
+        text
+    }
+
+    // One long table: `section` says which of the text report's tables a row
+    // belongs to, and `answers` is only filled in for consensus rows.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("section,key,groups,people,answers\n");
+        for q in &self.questions {
+            csv.push_str(&format!(
+                "question,{},{},{},\n",
+                q.question, q.groups, q.people
+            ));
+        }
+
+        for (size, groups) in &self.group_sizes {
+            csv.push_str(&format!("size,{size},{groups},{},\n", size * groups));
+        }
+
+        for c in &self.consensus {
+            csv.push_str(&format!(
+                "consensus,{},1,{},{}\n",
+                c.group,
+                c.size,
+                questions(c.answers)
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+    use anyhow::Result;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_report_testdata() -> Result<()> {
+        let report = Report::new(&parse_input(TESTDATA)?);
+
+        assert_eq!(
+            report.questions[..3],
+            [
+                QuestionStats {
+                    question: 'a',
+                    groups: 4,
+                    people: 8
+                },
+                QuestionStats {
+                    question: 'b',
+                    groups: 4,
+                    people: 4
+                },
+                QuestionStats {
+                    question: 'c',
+                    groups: 3,
+                    people: 3
+                },
+            ]
+        );
+        assert!(report.questions[3..].iter().all(|q| q.people == 0));
+        assert_eq!(
+            report.group_sizes.into_iter().collect::<Vec<_>>(),
+            vec![(1, 2), (2, 1), (3, 1), (4, 1)]
+        );
+        assert_eq!(
+            report.consensus.iter().map(|c| c.group).collect::<Vec<_>>(),
+            vec![0, 3, 4]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_formats() -> Result<()> {
+        let report = Report::new(&parse_input(TESTDATA)?);
+
+        let text = report.to_text();
+        assert!(text.starts_with("question groups people\n       a      4      8\n"));
+        assert!(text.contains("\n   4      1\n"));
+        assert!(text.ends_with(
+            "3 of 5 groups in full consensus\n\
+             Group 0 (1 people): abc\n\
+             Group 3 (4 people): a\n\
+             Group 4 (1 people): b\n"
+        ));
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with(
+            "section,key,groups,people,answers\n\
+             question,a,4,8,\n\
+             question,b,4,4,\n"
+        ));
+        assert!(csv.ends_with(
+            "question,z,0,0,\n\
+             size,1,2,2,\n\
+             size,2,1,2,\n\
+             size,3,1,3,\n\
+             size,4,1,4,\n\
+             consensus,0,1,1,abc\n\
+             consensus,3,1,4,a\n\
+             consensus,4,1,1,b\n"
+        ));
+        assert!(csv.lines().all(|line| line.split(',').count() == 5));
+
+        Ok(())
+    }
+}
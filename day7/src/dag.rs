@@ -0,0 +1,193 @@
+use crate::{graph::BagGraph, BagRuleItem};
+use anyhow::{Error, Result};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap},
+};
+
+#[derive(Debug)]
+pub struct BagDag {
+    graph: BagGraph,
+    order: Vec<usize>,
+}
+
+impl BagDag {
+    pub fn try_new(input: &HashMap<String, Vec<BagRuleItem>>) -> Result<Self> {
+        let graph = BagGraph::new(input);
+
+        // Kahn's algorithm, taking the lowest id first so the order is stable.
+        let mut incoming = graph
+            .ids()
+            .map(|id| graph.containers(id).len())
+            .collect::<Vec<usize>>();
+        let mut ready = graph
+            .ids()
+            .filter(|id| incoming[*id] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<Reverse<usize>>>();
+        let mut order = Vec::with_capacity(incoming.len());
+        while let Some(Reverse(id)) = ready.pop() {
+            order.push(id);
+            for (child, _) in graph.contents(id) {
+                incoming[*child] -= 1;
+                if incoming[*child] == 0 {
+                    ready.push(Reverse(*child));
+                }
+            }
+        }
+
+        if order.len() != incoming.len() {
+            let stuck = graph
+                .ids()
+                .filter(|id| incoming[*id] > 0)
+                .map(|id| graph.name(id))
+                .collect::<Vec<&str>>();
+            return Err(Error::msg(format!(
+                "Bag rules contain a cycle through {}",
+                stuck.join(", ")
+            )));
+        }
+
+        Ok(Self { graph, order })
+    }
+
+    pub fn topological_order(&self) -> Vec<&str> {
+        self.order.iter().map(|id| self.graph.name(*id)).collect()
+    }
+
+    // Number of nesting levels along the longest chain of bags.
+    pub fn max_depth(&self) -> usize {
+        let mut depths = vec![0; self.order.len()];
+        for id in self.order.iter().rev() {
+            depths[*id] = self
+                .graph
+                .contents(*id)
+                .iter()
+                .map(|(child, _)| depths[*child] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+
+        depths.into_iter().max().unwrap_or(0)
+    }
+
+    // The colour holding the most bags, and the colours whose contents overflow a u32.
+    pub fn largest_contents(&self) -> (Option<(&str, u32)>, Vec<&str>) {
+        let mut largest = None;
+        let mut overflowing = vec![];
+        for id in &self.order {
+            let name = self.graph.name(*id);
+            match self.graph.total_contents(name) {
+                Ok(total) if largest.is_none_or(|(_, max)| total > max) => {
+                    largest = Some((name, total));
+                }
+                Ok(_) => {}
+                Err(_) => overflowing.push(name),
+            }
+        }
+
+        (largest, overflowing)
+    }
+
+    // Every root counts as reachable from itself.
+    pub fn most_shared(&self) -> (Vec<&str>, usize) {
+        let mut roots = vec![BTreeSet::new(); self.order.len()];
+        for id in &self.order {
+            if self.graph.containers(*id).is_empty() {
+                roots[*id].insert(*id);
+            }
+            let reached = roots[*id].clone();
+            for (child, _) in self.graph.contents(*id) {
+                roots[*child].extend(reached.iter());
+            }
+        }
+
+        let most = roots.iter().map(BTreeSet::len).max().unwrap_or(0);
+        let colours = self
+            .graph
+            .ids()
+            .filter(|id| roots[*id].len() == most)
+            .map(|id| self.graph.name(id))
+            .collect();
+
+        (colours, most)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rules;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_analytics_testdata() -> Result<()> {
+        let dag = BagDag::try_new(&rules(TESTDATA)?)?;
+
+        assert_eq!(
+            dag.topological_order(),
+            vec![
+                "dark orange",
+                "light red",
+                "bright white",
+                "muted yellow",
+                "shiny gold",
+                "dark olive",
+                "vibrant plum",
+                "dotted black",
+                "faded blue"
+            ]
+        );
+        assert_eq!(dag.max_depth(), 4);
+        assert_eq!(dag.largest_contents(), (Some(("dark orange", 406)), vec![]));
+        assert_eq!(
+            dag.most_shared(),
+            (
+                vec![
+                    "bright white",
+                    "dark olive",
+                    "dotted black",
+                    "faded blue",
+                    "muted yellow",
+                    "shiny gold",
+                    "vibrant plum"
+                ],
+                2
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_largest_contents_overflow() -> Result<()> {
+        let dag = BagDag::try_new(&rules(
+            "dark red bags contain 1000 dark orange bags.\n\
+             dark orange bags contain 1000 dark yellow bags.\n\
+             dark yellow bags contain 1000 dark green bags.\n\
+             dark green bags contain 1000 dark blue bags.\n\
+             dark blue bags contain no other bags.",
+        )?)?;
+
+        assert_eq!(
+            dag.largest_contents(),
+            (Some(("dark orange", 1_001_001_000)), vec!["dark red"])
+        );
+        assert_eq!(dag.max_depth(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_cycle() -> Result<()> {
+        let dag = BagDag::try_new(&rules(
+            "light red bags contain 1 bright white bag.\n\
+             bright white bags contain 2 light red bags.",
+        )?);
+
+        assert!(dag.is_err());
+
+        Ok(())
+    }
+}
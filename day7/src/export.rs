@@ -0,0 +1,242 @@
+use crate::graph::BagGraph;
+use anyhow::{Error, Result};
+use std::{collections::VecDeque, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "mermaid" => Ok(Format::Mermaid),
+            _ => Err(Error::msg(format!("Unknown export format {s}"))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Role {
+    Focus,
+    Container,
+    Content,
+    Plain,
+}
+
+impl Role {
+    fn fill(&self) -> Option<&str> {
+        match self {
+            Role::Focus => Some("gold"),
+            Role::Container => Some("lightblue"),
+            Role::Content => Some("lightgreen"),
+            Role::Plain => None,
+        }
+    }
+}
+
+// With a focus colour the depth is counted away from it in both directions,
+// otherwise downwards from the outermost bags.
+pub fn export(
+    graph: &BagGraph,
+    format: Format,
+    focus: Option<&str>,
+    max_depth: Option<usize>,
+) -> Result<String> {
+    let within = |depth: Option<usize>| depth.is_some_and(|d| d <= max_depth.unwrap_or(usize::MAX));
+
+    let (included, roles) = match focus {
+        Some(colour) => {
+            let focus = graph.id(colour)?;
+            let up = distances(graph, &[focus], |id| graph.containers(id).to_vec());
+            let down = distances(graph, &[focus], |id| child_ids(graph, id));
+            let roles = graph
+                .ids()
+                .map(|id| match (id == focus, within(up[id]), within(down[id])) {
+                    (true, _, _) => Role::Focus,
+                    (_, true, _) => Role::Container,
+                    (_, _, true) => Role::Content,
+                    _ => Role::Plain,
+                })
+                .collect::<Vec<Role>>();
+            let included = graph
+                .ids()
+                .map(|id| max_depth.is_none() || roles[id] != Role::Plain)
+                .collect::<Vec<bool>>();
+            (included, roles)
+        }
+        None => {
+            let roots = graph
+                .ids()
+                .filter(|id| graph.containers(*id).is_empty())
+                .collect::<Vec<usize>>();
+            let down = distances(graph, &roots, |id| child_ids(graph, id));
+            let included = down
+                .iter()
+                .map(|depth| within(*depth))
+                .collect::<Vec<bool>>();
+            (included, vec![Role::Plain; graph.ids().len()])
+        }
+    };
+
+    Ok(match format {
+        Format::Dot => to_dot(graph, &included, &roles),
+        Format::Mermaid => to_mermaid(graph, &included, &roles),
+    })
+}
+
+fn child_ids(graph: &BagGraph, id: usize) -> Vec<usize> {
+    graph.contents(id).iter().map(|(child, _)| *child).collect()
+}
+
+fn distances(
+    graph: &BagGraph,
+    starts: &[usize],
+    next: impl Fn(usize) -> Vec<usize>,
+) -> Vec<Option<usize>> {
+    let mut depths = vec![None; graph.ids().len()];
+    let mut queue = VecDeque::new();
+    for start in starts {
+        depths[*start] = Some(0);
+        queue.push_back(*start);
+    }
+    while let Some(id) = queue.pop_front() {
+        let depth = depths[id].unwrap_or(0) + 1;
+        for neighbour in next(id) {
+            if depths[neighbour].is_none() {
+                depths[neighbour] = Some(depth);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    depths
+}
+
+fn edges<'a>(
+    graph: &'a BagGraph,
+    included: &'a [bool],
+) -> impl Iterator<Item = (usize, usize, u32)> + 'a {
+    graph.ids().filter(|id| included[*id]).flat_map(|id| {
+        graph
+            .contents(id)
+            .iter()
+            .filter(|(child, _)| included[*child])
+            .map(move |(child, amount)| (id, *child, *amount))
+    })
+}
+
+fn to_dot(graph: &BagGraph, included: &[bool], roles: &[Role]) -> String {
+    let mut dot = String::from("digraph bags {\n");
+    for id in graph.ids().filter(|id| included[*id]) {
+        match roles[id].fill() {
+            Some(fill) => dot.push_str(&format!(
+                "    {:?} [style=filled, fillcolor={fill}];\n",
+                graph.name(id)
+            )),
+            None => dot.push_str(&format!("    {:?};\n", graph.name(id))),
+        }
+    }
+    for (from, to, amount) in edges(graph, included) {
+        dot.push_str(&format!(
+            "    {:?} -> {:?} [label=\"{amount}\"];\n",
+            graph.name(from),
+            graph.name(to)
+        ));
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+fn to_mermaid(graph: &BagGraph, included: &[bool], roles: &[Role]) -> String {
+    let mut mermaid = String::from("graph TD\n");
+    for id in graph.ids().filter(|id| included[*id]) {
+        mermaid.push_str(&format!("    n{id}[\"{}\"]\n", graph.name(id)));
+    }
+    for (from, to, amount) in edges(graph, included) {
+        mermaid.push_str(&format!("    n{from} -->|{amount}| n{to}\n"));
+    }
+    for id in graph.ids().filter(|id| included[*id]) {
+        if let Some(fill) = roles[id].fill() {
+            mermaid.push_str(&format!("    style n{id} fill:{fill}\n"));
+        }
+    }
+
+    mermaid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_export_dot_testdata() -> Result<()> {
+        let graph = BagGraph::new(&parse_input(TESTDATA)?);
+        let dot = export(&graph, Format::Dot, None, None)?;
+
+        assert!(dot.starts_with("digraph bags {\n    \"bright white\";\n"));
+        assert!(dot.contains("    \"light red\" -> \"muted yellow\" [label=\"2\"];\n"));
+        assert_eq!(dot.matches(" -> ").count(), 13);
+        assert!(dot.ends_with("}\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_highlight_testdata() -> Result<()> {
+        let graph = BagGraph::new(&parse_input(TESTDATA)?);
+
+        let dot = export(&graph, Format::Dot, Some("shiny gold"), None)?;
+        assert!(dot.contains("    \"shiny gold\" [style=filled, fillcolor=gold];\n"));
+        assert!(dot.contains("    \"light red\" [style=filled, fillcolor=lightblue];\n"));
+        assert!(dot.contains("    \"faded blue\" [style=filled, fillcolor=lightgreen];\n"));
+        assert_eq!(dot.matches(" -> ").count(), 13);
+
+        let dot = export(&graph, Format::Dot, Some("shiny gold"), Some(1))?;
+        assert!(dot.contains("    \"muted yellow\" [style=filled, fillcolor=lightblue];\n"));
+        assert!(dot.contains("    \"dark olive\" [style=filled, fillcolor=lightgreen];\n"));
+        assert!(!dot.contains("light red"));
+        assert!(!dot.contains("faded blue"));
+        assert_eq!(dot.matches(" -> ").count(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_mermaid_testdata() -> Result<()> {
+        let graph = BagGraph::new(&parse_input(TESTDATA)?);
+        let mermaid = export(&graph, Format::Mermaid, None, Some(0))?;
+
+        assert_eq!(
+            mermaid,
+            "graph TD\n    n2[\"dark orange\"]\n    n5[\"light red\"]\n"
+        );
+
+        let mermaid = export(&graph, Format::Mermaid, Some("dark olive"), Some(1))?;
+        assert_eq!(
+            mermaid,
+            "graph TD\n    \
+             n1[\"dark olive\"]\n    \
+             n3[\"dotted black\"]\n    \
+             n4[\"faded blue\"]\n    \
+             n7[\"shiny gold\"]\n    \
+             n1 -->|3| n4\n    \
+             n1 -->|4| n3\n    \
+             n7 -->|1| n1\n    \
+             style n1 fill:gold\n    \
+             style n3 fill:lightgreen\n    \
+             style n4 fill:lightgreen\n    \
+             style n7 fill:lightblue\n"
+        );
+
+        Ok(())
+    }
+}
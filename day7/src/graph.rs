@@ -0,0 +1,164 @@
+use crate::BagRuleItem;
+use anyhow::{Error, Result};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    ops::Range,
+    rc::Rc,
+};
+
+#[derive(Debug)]
+pub struct BagGraph {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    contents: Vec<Vec<(usize, u32)>>,
+    containers: Vec<Vec<usize>>,
+    containers_memo: RefCell<Vec<Option<Rc<BTreeSet<usize>>>>>,
+    total_memo: RefCell<Vec<Option<u32>>>,
+}
+
+impl BagGraph {
+    pub fn new(input: &HashMap<String, Vec<BagRuleItem>>) -> Self {
+        // Sorted so that ids do not depend on the hash map's iteration order.
+        let names = input
+            .iter()
+            .flat_map(|(name, contains)| {
+                std::iter::once(name).chain(contains.iter().map(|item| &item.name))
+            })
+            .collect::<BTreeSet<&String>>()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<String>>();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect::<HashMap<String, usize>>();
+
+        let mut contents = vec![vec![]; names.len()];
+        let mut containers = vec![vec![]; names.len()];
+        for (name, contains) in input {
+            let id = ids[name];
+            for item in contains {
+                let child = ids[&item.name];
+                contents[id].push((child, item.amount));
+                containers[child].push(id);
+            }
+        }
+
+        Self {
+            containers_memo: RefCell::new(vec![None; names.len()]),
+            total_memo: RefCell::new(vec![None; names.len()]),
+            names,
+            ids,
+            contents,
+            containers,
+        }
+    }
+
+    pub fn id(&self, colour: &str) -> Result<usize> {
+        self.ids
+            .get(colour)
+            .copied()
+            .ok_or_else(|| Error::msg(format!("Unknown colour {colour}")))
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn ids(&self) -> Range<usize> {
+        0..self.names.len()
+    }
+
+    pub fn contents(&self, id: usize) -> &[(usize, u32)] {
+        &self.contents[id]
+    }
+
+    pub fn containers(&self, id: usize) -> &[usize] {
+        &self.containers[id]
+    }
+
+    pub fn containers_of(&self, colour: &str) -> Result<Vec<&str>> {
+        let containers = self.all_containers(self.id(colour)?);
+
+        Ok(containers.iter().map(|id| self.name(*id)).collect())
+    }
+
+    pub fn total_contents(&self, colour: &str) -> Result<u32> {
+        self.total(self.id(colour)?)
+            .ok_or_else(|| Error::msg(format!("Contents of {colour} overflow a u32")))
+    }
+
+    fn all_containers(&self, id: usize) -> Rc<BTreeSet<usize>> {
+        if let Some(containers) = &self.containers_memo.borrow()[id] {
+            return Rc::clone(containers);
+        }
+
+        let mut containers = BTreeSet::new();
+        for parent in &self.containers[id] {
+            containers.insert(*parent);
+            containers.extend(self.all_containers(*parent).iter());
+        }
+        let containers = Rc::new(containers);
+        self.containers_memo.borrow_mut()[id] = Some(Rc::clone(&containers));
+
+        containers
+    }
+
+    fn total(&self, id: usize) -> Option<u32> {
+        if let Some(total) = self.total_memo.borrow()[id] {
+            return Some(total);
+        }
+
+        let total = self.contents[id]
+            .iter()
+            .try_fold(0u32, |total, (child, amount)| {
+                let inside = self.total(*child)?.checked_add(1)?;
+                total.checked_add(amount.checked_mul(inside)?)
+            })?;
+        self.total_memo.borrow_mut()[id] = Some(total);
+
+        Some(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_queries_testdata() -> Result<()> {
+        let graph = BagGraph::new(&parse_input(TESTDATA)?);
+
+        assert_eq!(graph.names.len(), 9);
+        assert_eq!(
+            graph.containers_of("shiny gold")?,
+            vec!["bright white", "dark orange", "light red", "muted yellow"]
+        );
+        assert_eq!(graph.containers_of("light red")?, Vec::<&str>::new());
+        assert_eq!(graph.total_contents("shiny gold")?, 32);
+        assert_eq!(graph.total_contents("dark olive")?, 7);
+        assert_eq!(graph.total_contents("faded blue")?, 0);
+        assert!(graph.total_contents("plaid mauve").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_total_contents_overflow() -> Result<()> {
+        let graph = BagGraph::new(&parse_input(
+            "light red bags contain 65536 bright white bags.\n\
+             bright white bags contain 65536 faded blue bags.\n\
+             faded blue bags contain no other bags.",
+        )?);
+
+        assert_eq!(graph.total_contents("bright white")?, 65536);
+        assert!(graph.total_contents("light red").is_err());
+
+        Ok(())
+    }
+}
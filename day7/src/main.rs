@@ -1,5 +1,8 @@
-use anyhow::Result;
-use itertools::Itertools;
+#![cfg_attr(test, feature(test))]
+
+use anyhow::{Error, Result};
+use dag::BagDag;
+use graph::BagGraph;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -9,103 +12,88 @@ use nom::{
     sequence::{separated_pair, terminated},
     IResult, Parser,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, env};
+use validate::{validate, RuleIssue};
+
+mod dag;
+mod export;
+mod graph;
+mod validate;
 
 const DATA: &str = include_str!("input.txt");
+const TARGET: &str = "shiny gold";
 
 fn main() -> Result<()> {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let colour = args
+        .iter()
+        .skip_while(|arg| *arg != "--colour")
+        .nth(1)
+        .map_or(TARGET, String::as_str);
+
+    if args.iter().any(|arg| arg == "--validate") {
+        let (_, rules) = parse(DATA)?;
+        for issue in validate(&rules) {
+            println!("{issue}");
+        }
+    }
+
     let (took, result) = took::took(|| parse_input(DATA));
     println!("Time spent parsing: {}", took);
-    let input = result?;
+    let rules = result?;
+    let input = BagGraph::new(&rules);
 
-    let (took, result) = took::took(|| part_one(&input));
-    println!("Result part one: {result}");
+    let (took, result) = took::took(|| part_one(&input, colour));
+    println!("Result part one: {}", result?);
     println!("Time spent: {took}");
 
-    let (took, result) = took::took(|| part_two(&input));
-    println!("Result part two: {result}");
+    let (took, result) = took::took(|| part_two(&input, colour));
+    println!("Result part two: {}", result?);
     println!("Time spent: {took}");
 
-    Ok(())
-}
-
-fn part_one(input: &HashMap<String, Vec<BagRuleItem>>) -> usize {
-    let mut rev_input: HashMap<String, Vec<String>> = HashMap::new();
-    for (name, contains) in input {
-        for c in contains {
-            rev_input
-                .entry(c.name.clone())
-                .or_default()
-                .push(name.clone());
+    if args.iter().any(|arg| arg == "--analyze") {
+        let dag = BagDag::try_new(&rules)?;
+        println!("Topological order: {}", dag.topological_order().join(", "));
+        println!("Maximum nesting depth: {}", dag.max_depth());
+        let (largest, overflowing) = dag.largest_contents();
+        if let Some((colour, total)) = largest {
+            println!("Largest contents: {colour} holds {total} bags");
+        }
+        if !overflowing.is_empty() {
+            println!("Contents overflow a u32: {}", overflowing.join(", "));
         }
+        let (colours, roots) = dag.most_shared();
+        println!("Reachable from {roots} roots: {}", colours.join(", "));
     }
 
-    flatten(&rev_input).len() - 1
-}
-
-fn flatten(rev_input: &HashMap<String, Vec<String>>) -> Vec<String> {
-    traverse(rev_input, vec![], vec![String::from("shiny gold")])
-        .iter()
-        .flatten()
-        .unique()
-        .cloned()
-        .collect::<Vec<String>>()
-}
-
-fn traverse(
-    rev_input: &HashMap<String, Vec<String>>,
-    current: Vec<String>,
-    keys: Vec<String>,
-) -> Vec<Vec<String>> {
-    if keys.is_empty() {
-        return vec![current];
-    }
-
-    keys.iter()
-        .flat_map(|key| {
-            let mut new_current = current.clone();
-            new_current.push(key.to_owned());
-            match rev_input.get(key) {
-                None => vec![new_current],
-                Some(new_keys) => traverse(rev_input, new_current, new_keys.to_owned()),
-            }
-        })
-        .collect::<Vec<Vec<String>>>()
-}
-
-fn part_two(input: &HashMap<String, Vec<BagRuleItem>>) -> u32 {
-    let result = traverse_bags(input, 1, "shiny gold");
-
-    result.calculate() - 1
-}
+    if let Some(format) = args.iter().skip_while(|arg| *arg != "--export").nth(1) {
+        let focus = args.iter().skip_while(|arg| *arg != "--highlight").nth(1);
+        let max_depth = args
+            .iter()
+            .skip_while(|arg| *arg != "--max-depth")
+            .nth(1)
+            .map(|depth| depth.parse::<usize>())
+            .transpose()?;
+        print!(
+            "{}",
+            export::export(
+                &input,
+                format.parse()?,
+                focus.map(String::as_str),
+                max_depth
+            )?
+        );
+    }
 
-fn traverse_bags(input: &HashMap<String, Vec<BagRuleItem>>, amount: u32, key: &str) -> Bag {
-    let bags = input[key]
-        .iter()
-        .map(|b| traverse_bags(input, b.amount, b.name.as_str()))
-        .collect::<Vec<Bag>>();
-    Bag::new(amount, bags)
+    Ok(())
 }
 
-#[derive(Debug)]
-struct Bag {
-    amount: u32,
-    contains: Vec<Bag>,
+fn part_one(input: &BagGraph, colour: &str) -> Result<usize> {
+    Ok(input.containers_of(colour)?.len())
 }
 
-impl Bag {
-    pub fn new(amount: u32, contains: Vec<Bag>) -> Self {
-        Self { amount, contains }
-    }
-
-    pub fn calculate(&self) -> u32 {
-        self.amount
-            + self
-                .contains
-                .iter()
-                .map(|b| self.amount * b.calculate())
-                .sum::<u32>()
-    }
+fn part_two(input: &BagGraph, colour: &str) -> Result<u32> {
+    input.total_contents(colour)
 }
 
 #[derive(Clone, Debug)]
@@ -177,6 +165,14 @@ fn parse_amount_plus_bag(input: &str) -> IResult<&str, (u32, &str)> {
 
 fn parse_input(input: &'static str) -> Result<HashMap<String, Vec<BagRuleItem>>> {
     let (_, input) = parse(input)?;
+    let fatal = validate(&input)
+        .iter()
+        .filter(|issue| issue.is_fatal())
+        .map(RuleIssue::to_string)
+        .collect::<Vec<String>>();
+    if !fatal.is_empty() {
+        return Err(Error::msg(format!("Invalid rules: {}", fatal.join(", "))));
+    }
 
     Ok(input)
 }
@@ -184,34 +180,192 @@ fn parse_input(input: &'static str) -> Result<HashMap<String, Vec<BagRuleItem>>>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
+    use test::Bencher;
+
+    extern crate test;
 
     const TESTDATA: &str = include_str!("test.txt");
 
+    // The rules without the checks `parse_input` makes, to test broken rules.
+    pub fn rules(input: &str) -> Result<HashMap<String, Vec<BagRuleItem>>> {
+        let (_, rules) = parse(input).map_err(|e| Error::msg(e.to_string()))?;
+
+        Ok(rules)
+    }
+
     #[test]
     fn test_part_one_testdata() -> Result<()> {
-        assert_eq!(part_one(&parse_input(TESTDATA)?), 4);
+        assert_eq!(
+            part_one(&BagGraph::new(&parse_input(TESTDATA)?), TARGET)?,
+            4
+        );
 
         Ok(())
     }
 
     #[test]
     fn test_part_one() -> Result<()> {
-        assert_eq!(part_one(&parse_input(DATA)?), 233);
+        assert_eq!(part_one(&BagGraph::new(&parse_input(DATA)?), TARGET)?, 233);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two_testdata() -> Result<()> {
-        assert_eq!(part_two(&parse_input(TESTDATA)?), 32);
+        assert_eq!(
+            part_two(&BagGraph::new(&parse_input(TESTDATA)?), TARGET)?,
+            32
+        );
 
         Ok(())
     }
 
     #[test]
     fn test_part_two() -> Result<()> {
-        assert_eq!(part_two(&parse_input(DATA)?), 421550);
+        assert_eq!(
+            part_two(&BagGraph::new(&parse_input(DATA)?), TARGET)?,
+            421550
+        );
 
         Ok(())
     }
+
+    #[test]
+    fn test_reject_invalid_rules() {
+        assert!(parse_input("light red bags contain 1 plaid mauve bag.").is_err());
+        assert!(parse_input(
+            "light red bags contain 1 bright white bag.\n\
+             bright white bags contain 2 light red bags."
+        )
+        .is_err());
+    }
+
+    fn naive_containers(input: &HashMap<String, Vec<BagRuleItem>>, colour: &str) -> usize {
+        let mut rev_input: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, contains) in input {
+            for c in contains {
+                rev_input
+                    .entry(c.name.clone())
+                    .or_default()
+                    .push(name.clone());
+            }
+        }
+
+        traverse(&rev_input, vec![], vec![colour.to_string()])
+            .iter()
+            .flatten()
+            .unique()
+            .count()
+            - 1
+    }
+
+    fn traverse(
+        rev_input: &HashMap<String, Vec<String>>,
+        current: Vec<String>,
+        keys: Vec<String>,
+    ) -> Vec<Vec<String>> {
+        if keys.is_empty() {
+            return vec![current];
+        }
+
+        keys.iter()
+            .flat_map(|key| {
+                let mut new_current = current.clone();
+                new_current.push(key.to_owned());
+                match rev_input.get(key) {
+                    None => vec![new_current],
+                    Some(new_keys) => traverse(rev_input, new_current, new_keys.to_owned()),
+                }
+            })
+            .collect::<Vec<Vec<String>>>()
+    }
+
+    fn naive_total(input: &HashMap<String, Vec<BagRuleItem>>, colour: &str) -> u32 {
+        input[colour]
+            .iter()
+            .map(|b| b.amount * (1 + naive_total(input, &b.name)))
+            .sum()
+    }
+
+    // Layers of colours where every colour holds two colours of the next layer,
+    // picked with different strides so that colours share contents.
+    fn synthetic_rules(layers: usize, width: usize) -> HashMap<String, Vec<BagRuleItem>> {
+        (0..layers)
+            .flat_map(|layer| (0..width).map(move |idx| (layer, idx)))
+            .map(|(layer, idx)| {
+                let contains = if layer + 1 == layers {
+                    vec![]
+                } else {
+                    (0..2)
+                        .map(|k| {
+                            let colour = (idx * (2 * k + 3) + layer * (k + 1) + k) % width;
+                            BagRuleItem::new(
+                                1 + ((idx + layer + k) % 2) as u32,
+                                format!("layer{} colour{colour}", layer + 1),
+                            )
+                        })
+                        .collect()
+                };
+                (format!("layer{layer} colour{idx}"), contains)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_graph_synthetic() {
+        let rules = synthetic_rules(8, 10);
+        let graph = BagGraph::new(&rules);
+        for idx in 0..10 {
+            let top = format!("layer0 colour{idx}");
+            let bottom = format!("layer7 colour{idx}");
+            assert_eq!(
+                part_one(&graph, &bottom).ok(),
+                Some(naive_containers(&rules, &bottom))
+            );
+            assert_eq!(part_two(&graph, &top).ok(), Some(naive_total(&rules, &top)));
+        }
+    }
+
+    #[bench]
+    fn bench_containers_graph(b: &mut Bencher) {
+        let rules = synthetic_rules(12, 20);
+        b.iter(|| {
+            let graph = BagGraph::new(&rules);
+            (0..20)
+                .map(|idx| part_one(&graph, &format!("layer11 colour{idx}")).unwrap_or(0))
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    fn bench_containers_naive(b: &mut Bencher) {
+        let rules = synthetic_rules(12, 20);
+        b.iter(|| {
+            (0..20)
+                .map(|idx| naive_containers(&rules, &format!("layer11 colour{idx}")))
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    fn bench_total_graph(b: &mut Bencher) {
+        let rules = synthetic_rules(12, 20);
+        b.iter(|| {
+            let graph = BagGraph::new(&rules);
+            (0..20)
+                .map(|idx| part_two(&graph, &format!("layer0 colour{idx}")).unwrap_or(0))
+                .sum::<u32>()
+        });
+    }
+
+    #[bench]
+    fn bench_total_naive(b: &mut Bencher) {
+        let rules = synthetic_rules(12, 20);
+        b.iter(|| {
+            (0..20)
+                .map(|idx| naive_total(&rules, &format!("layer0 colour{idx}")))
+                .sum::<u32>()
+        });
+    }
 }
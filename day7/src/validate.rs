@@ -0,0 +1,184 @@
+use crate::BagRuleItem;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum RuleIssue {
+    Undefined {
+        colour: String,
+        referenced_by: String,
+    },
+    Cycle {
+        path: Vec<String>,
+    },
+    Unreferenced {
+        colour: String,
+    },
+}
+
+impl RuleIssue {
+    // Outermost bags are never referenced, so that alone does not break the rules.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, RuleIssue::Unreferenced { .. })
+    }
+}
+
+impl fmt::Display for RuleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleIssue::Undefined {
+                colour,
+                referenced_by,
+            } => write!(f, "{referenced_by} contains undefined colour {colour}"),
+            RuleIssue::Cycle { path } => write!(f, "cycle {}", path.join(" -> ")),
+            RuleIssue::Unreferenced { colour } => write!(f, "{colour} is never referenced"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Unvisited,
+    OnPath,
+    Done,
+}
+
+pub fn validate(input: &HashMap<String, Vec<BagRuleItem>>) -> Vec<RuleIssue> {
+    let mut colours = input.keys().collect::<Vec<&String>>();
+    colours.sort();
+
+    let mut issues = vec![];
+    for colour in &colours {
+        for item in &input[*colour] {
+            if !input.contains_key(&item.name) {
+                issues.push(RuleIssue::Undefined {
+                    colour: item.name.clone(),
+                    referenced_by: colour.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut marks = colours
+        .iter()
+        .map(|colour| (colour.as_str(), Mark::Unvisited))
+        .collect::<HashMap<&str, Mark>>();
+    for colour in &colours {
+        find_cycles(input, colour, &mut marks, &mut vec![], &mut issues);
+    }
+
+    let referenced = input
+        .values()
+        .flatten()
+        .map(|item| item.name.as_str())
+        .collect::<HashSet<&str>>();
+    for colour in colours {
+        if !referenced.contains(colour.as_str()) {
+            issues.push(RuleIssue::Unreferenced {
+                colour: colour.clone(),
+            });
+        }
+    }
+
+    issues
+}
+
+fn find_cycles<'a>(
+    input: &'a HashMap<String, Vec<BagRuleItem>>,
+    colour: &'a str,
+    marks: &mut HashMap<&'a str, Mark>,
+    path: &mut Vec<&'a str>,
+    issues: &mut Vec<RuleIssue>,
+) {
+    match marks.get(colour) {
+        Some(Mark::Unvisited) => {}
+        Some(Mark::OnPath) => {
+            let start = path.iter().position(|c| *c == colour).unwrap_or(0);
+            let mut cycle = path[start..]
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>();
+            cycle.push(colour.to_string());
+            issues.push(RuleIssue::Cycle { path: cycle });
+            return;
+        }
+        // Done, or undefined which has already been reported.
+        _ => return,
+    }
+
+    marks.insert(colour, Mark::OnPath);
+    path.push(colour);
+    for item in &input[colour] {
+        find_cycles(input, &item.name, marks, path, issues);
+    }
+    path.pop();
+    marks.insert(colour, Mark::Done);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rules;
+    use anyhow::Result;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_validate_testdata() -> Result<()> {
+        let issues = validate(&rules(TESTDATA)?);
+
+        assert!(issues.iter().all(|issue| !issue.is_fatal()));
+        assert_eq!(
+            issues,
+            vec![
+                RuleIssue::Unreferenced {
+                    colour: String::from("dark orange")
+                },
+                RuleIssue::Unreferenced {
+                    colour: String::from("light red")
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_broken_rules() -> Result<()> {
+        let issues = validate(&rules(
+            "light red bags contain 1 bright white bag, 2 plaid mauve bags.\n\
+             bright white bags contain 1 shiny gold bag.\n\
+             shiny gold bags contain 2 dark olive bags.\n\
+             dark olive bags contain 3 bright white bags.",
+        )?);
+
+        assert_eq!(
+            issues,
+            vec![
+                RuleIssue::Undefined {
+                    colour: String::from("plaid mauve"),
+                    referenced_by: String::from("light red")
+                },
+                RuleIssue::Cycle {
+                    path: vec![
+                        String::from("bright white"),
+                        String::from("shiny gold"),
+                        String::from("dark olive"),
+                        String::from("bright white")
+                    ]
+                },
+                RuleIssue::Unreferenced {
+                    colour: String::from("light red")
+                },
+            ]
+        );
+        assert_eq!(
+            issues[1].to_string(),
+            "cycle bright white -> shiny gold -> dark olive -> bright white"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use crate::Instruction;
+use anyhow::{Error, Result};
+use nom::{
+    branch::alt,
+    bytes::complete::take_while,
+    character::complete::{self, alpha1, char, satisfy, space0, space1},
+    combinator::{all_consuming, map, opt, recognize, rest},
+    sequence::{pair, preceded, terminated},
+    IResult, Parser,
+};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, PartialEq)]
+enum Operand<'a> {
+    Value(i16),
+    Label(&'a str),
+}
+
+#[derive(Debug, PartialEq)]
+struct Line<'a> {
+    label: Option<&'a str>,
+    operation: Option<(&'a str, Operand<'a>)>,
+}
+
+// Labels are written as `name:` in front of an instruction or on a line of their
+// own, comments start with `#` or `;`, and jmp/nop accept a label as operand.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            all_consuming(parse_line)
+                .parse(line)
+                .map(|(_, line)| line)
+                .map_err(|_| Error::msg(format!("Line {}: cannot parse {line:?}", idx + 1)))
+        })
+        .collect::<Result<Vec<Line>>>()?;
+
+    let mut labels = HashMap::new();
+    let mut address = 0;
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(label) = line.label
+            && labels.insert(label, address).is_some()
+        {
+            return Err(Error::msg(format!(
+                "Line {}: label {label} is defined twice",
+                idx + 1
+            )));
+        }
+        if line.operation.is_some() {
+            address += 1;
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, line)| Some((idx, line.operation.as_ref()?)))
+        .enumerate()
+        .map(|(address, (idx, (mnemonic, operand)))| {
+            let value = match operand {
+                Operand::Value(val) => *val,
+                Operand::Label(label) if *mnemonic != "acc" => {
+                    let target = labels.get(label).ok_or_else(|| {
+                        Error::msg(format!("Line {}: undefined label {label}", idx + 1))
+                    })?;
+                    i16::try_from(*target as isize - address as isize).map_err(|_| {
+                        Error::msg(format!("Line {}: {label} is too far away", idx + 1))
+                    })?
+                }
+                Operand::Label(_) => {
+                    return Err(Error::msg(format!(
+                        "Line {}: acc needs a number, not a label",
+                        idx + 1
+                    )))
+                }
+            };
+            match *mnemonic {
+                "acc" => Ok(Instruction::Acc(value)),
+                "jmp" => Ok(Instruction::Jmp(value)),
+                "nop" => Ok(Instruction::Nop(value)),
+                _ => Err(Error::msg(format!(
+                    "Line {}: unknown opcode {mnemonic}",
+                    idx + 1
+                ))),
+            }
+        })
+        .collect()
+}
+
+// Every jmp target inside the program (or right after it) gets a label `L<index>`.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let targets = program
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| matches!(instruction, Instruction::Jmp(_)))
+        .filter_map(|(idx, instruction)| usize::try_from(instruction.target(idx)).ok())
+        .filter(|target| *target <= program.len())
+        .collect::<BTreeSet<usize>>();
+
+    let mut source = String::new();
+    for (idx, instruction) in program.iter().enumerate() {
+        if targets.contains(&idx) {
+            source.push_str(&format!("L{idx}:\n"));
+        }
+        match instruction {
+            Instruction::Jmp(_) if targets.contains(&(instruction.target(idx) as usize)) => {
+                source.push_str(&format!("    jmp L{}\n", instruction.target(idx)));
+            }
+            _ => source.push_str(&format!("    {instruction}\n")),
+        }
+    }
+    if targets.contains(&program.len()) {
+        source.push_str(&format!("L{}:\n", program.len()));
+    }
+
+    source
+}
+
+fn parse_line(input: &str) -> IResult<&str, Line<'_>> {
+    map(
+        (
+            space0,
+            opt(terminated(parse_label, pair(char(':'), space0))),
+            opt(terminated(parse_operation, space0)),
+            opt(parse_comment),
+        ),
+        |(_, label, operation, _)| Line { label, operation },
+    )
+    .parse(input)
+}
+
+fn parse_operation(input: &str) -> IResult<&str, (&str, Operand<'_>)> {
+    pair(terminated(alpha1, space1), parse_operand).parse(input)
+}
+
+fn parse_operand(input: &str) -> IResult<&str, Operand<'_>> {
+    alt((
+        map(complete::i16, Operand::Value),
+        map(parse_label, Operand::Label),
+    ))
+    .parse(input)
+}
+
+fn parse_label(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+    ))
+    .parse(input)
+}
+
+fn parse_comment(input: &str) -> IResult<&str, &str> {
+    preceded(alt((char('#'), char(';'))), rest).parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, DATA};
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_assemble_labels() -> Result<()> {
+        let program = assemble(
+            "# labels and comments\n\
+             start:  acc +1\n\
+             \n\
+             loop:\n    \
+                 nop end   ; not taken\n    \
+                 jmp loop\n\
+             end:",
+        )?;
+
+        assert_eq!(
+            program,
+            vec![
+                Instruction::Acc(1),
+                Instruction::Nop(2),
+                Instruction::Jmp(-1)
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert!(assemble("jmp nowhere").is_err());
+        assert!(assemble("acc start\nstart: nop +0").is_err());
+        assert!(assemble("a: nop +0\na: nop +0").is_err());
+        assert!(assemble("mul +2").is_err());
+        assert!(assemble("jmp +1 +2").is_err());
+    }
+
+    #[test]
+    fn test_disassemble_testdata() -> Result<()> {
+        let program = parse_input(TESTDATA)?;
+
+        assert_eq!(
+            disassemble(&program),
+            "    nop +0\n\
+             L1:\n    acc +1\n    jmp L6\n\
+             L3:\n    acc +3\n    jmp L1\n    acc -99\n\
+             L6:\n    acc +1\n    jmp L3\n    acc +6\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        for data in [TESTDATA, DATA] {
+            let program = parse_input(data)?;
+
+            assert_eq!(assemble(data)?, program);
+            assert_eq!(assemble(&disassemble(&program))?, program);
+            let plain = program
+                .iter()
+                .map(Instruction::to_string)
+                .collect::<Vec<String>>();
+            assert_eq!(plain, data.lines().collect::<Vec<&str>>());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,362 @@
+use crate::Instruction;
+use std::collections::{BTreeSet, VecDeque};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exit {
+    Block(usize),
+    Terminate,
+    OutOfBounds(isize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub next: Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathEnd {
+    Loop(usize),
+    Terminate,
+    OutOfBounds(isize),
+}
+
+// Every instruction has exactly one successor, so the graph is a set of chains
+// that each end in a loop, at the end of the program or outside of it.
+pub struct Cfg<'a> {
+    program: &'a [Instruction],
+    blocks: Vec<Block>,
+    path: Vec<usize>,
+    path_end: PathEnd,
+    terminating: Vec<bool>,
+}
+
+impl<'a> Cfg<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
+        let len = program.len();
+        let in_range = |target: isize| usize::try_from(target).ok().filter(|t| *t < len);
+
+        let mut leaders = BTreeSet::from([0]);
+        for (idx, instruction) in program.iter().enumerate() {
+            if let Instruction::Jmp(_) = instruction {
+                leaders.extend(in_range(instruction.target(idx)));
+                leaders.insert(idx + 1);
+            }
+        }
+        let leaders = leaders
+            .into_iter()
+            .filter(|idx| *idx < len)
+            .collect::<Vec<usize>>();
+
+        let block_of = |idx: usize| leaders.partition_point(|leader| *leader <= idx) - 1;
+        let blocks = leaders
+            .iter()
+            .enumerate()
+            .map(|(block, start)| {
+                let end = leaders.get(block + 1).copied().unwrap_or(len);
+                let target = program[end - 1].target(end - 1);
+                let next = match in_range(target) {
+                    Some(target) => Exit::Block(block_of(target)),
+                    None if target == len as isize => Exit::Terminate,
+                    None => Exit::OutOfBounds(target),
+                };
+                Block {
+                    start: *start,
+                    end,
+                    next,
+                }
+            })
+            .collect::<Vec<Block>>();
+
+        let mut seen = vec![false; len];
+        let mut path = vec![];
+        let mut idx = 0isize;
+        let path_end = loop {
+            match in_range(idx) {
+                Some(i) if seen[i] => break PathEnd::Loop(i),
+                Some(i) => {
+                    seen[i] = true;
+                    path.push(i);
+                    idx = program[i].target(i);
+                }
+                None if idx == len as isize => break PathEnd::Terminate,
+                None => break PathEnd::OutOfBounds(idx),
+            }
+        };
+
+        Self {
+            program,
+            blocks,
+            path,
+            path_end,
+            terminating: terminating(program),
+        }
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    // Instructions executed from index 0, in order.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    pub fn path_end(&self) -> PathEnd {
+        self.path_end
+    }
+
+    // Each loop as the blocks it runs through, starting from its lowest block.
+    pub fn loops(&self) -> Vec<Vec<usize>> {
+        let mut walk_of = vec![None; self.blocks.len()];
+        let mut loops = vec![];
+        for start in 0..self.blocks.len() {
+            let mut block = start;
+            while walk_of[block].is_none() {
+                walk_of[block] = Some(start);
+                match self.blocks[block].next {
+                    Exit::Block(next) => block = next,
+                    _ => break,
+                }
+            }
+            if walk_of[block] == Some(start) && matches!(self.blocks[block].next, Exit::Block(_)) {
+                let mut cycle = vec![block];
+                let mut current = block;
+                while let Exit::Block(next) = self.blocks[current].next {
+                    if next == block {
+                        break;
+                    }
+                    cycle.push(next);
+                    current = next;
+                }
+                let lowest = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap_or(0);
+                cycle.rotate_left(lowest);
+                loops.push(cycle);
+            }
+        }
+        loops.sort();
+
+        loops
+    }
+
+    pub fn unreachable(&self) -> Vec<usize> {
+        let mut reachable = vec![false; self.program.len()];
+        for idx in &self.path {
+            reachable[*idx] = true;
+        }
+
+        (0..self.program.len())
+            .filter(|idx| !reachable[*idx])
+            .collect()
+    }
+
+    pub fn can_terminate(&self, idx: usize) -> bool {
+        self.terminating.get(idx).copied().unwrap_or(false)
+    }
+
+    pub fn terminating(&self) -> Vec<usize> {
+        (0..self.program.len())
+            .filter(|idx| self.terminating[*idx])
+            .collect()
+    }
+
+    // Part one: the acc values along the path, if it loops.
+    pub fn looping_acc(&self) -> Option<i32> {
+        matches!(self.path_end, PathEnd::Loop(_)).then(|| self.acc(&self.path))
+    }
+
+    // Part two: the first jmp/nop on the path whose swapped target can terminate.
+    // Nothing after that target was on the path, so the swapped instruction is
+    // never executed again.
+    pub fn repair(&self) -> Option<(usize, i32)> {
+        let (position, idx) = self.path.iter().enumerate().find(|(_, idx)| {
+            self.program[**idx]
+                .swapped()
+                .and_then(|swapped| usize::try_from(swapped.target(**idx)).ok())
+                .is_some_and(|target| self.terminating.get(target).copied().unwrap_or(false))
+        })?;
+
+        let mut rest = vec![];
+        let mut next = self.program[*idx].swapped()?.target(*idx) as usize;
+        while next < self.program.len() {
+            rest.push(next);
+            next = self.program[next].target(next) as usize;
+        }
+
+        Some((*idx, self.acc(&self.path[..position]) + self.acc(&rest)))
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+        let on_path = self.path.iter().copied().collect::<BTreeSet<usize>>();
+        for (id, block) in self.blocks.iter().enumerate() {
+            let label = (block.start..block.end)
+                .map(|idx| format!("{idx}: {}\\l", self.program[idx]))
+                .collect::<String>();
+            let style = match (
+                on_path.contains(&block.start),
+                self.can_terminate(block.start),
+            ) {
+                (true, true) => ", style=filled, fillcolor=lightgreen",
+                (true, false) => "",
+                (false, true) => ", style=\"filled,dashed\", fillcolor=lightgreen",
+                (false, false) => ", style=dashed",
+            };
+            dot.push_str(&format!("    b{id} [label=\"{label}\"{style}];\n"));
+        }
+        dot.push_str("    exit [shape=doublecircle];\n");
+        for (id, block) in self.blocks.iter().enumerate() {
+            match block.next {
+                Exit::Block(next) => dot.push_str(&format!("    b{id} -> b{next};\n")),
+                Exit::Terminate => dot.push_str(&format!("    b{id} -> exit;\n")),
+                Exit::OutOfBounds(target) => {
+                    dot.push_str(&format!("    b{id} -> out{id};\n"));
+                    dot.push_str(&format!(
+                        "    out{id} [label=\"{target}\", shape=plaintext];\n"
+                    ));
+                }
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    fn acc(&self, indices: &[usize]) -> i32 {
+        indices
+            .iter()
+            .map(|idx| match self.program[*idx] {
+                Instruction::Acc(val) => val as i32,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+// Whether execution starting at each index (and one past the end) terminates.
+fn terminating(program: &[Instruction]) -> Vec<bool> {
+    let mut predecessors = vec![vec![]; program.len() + 1];
+    for (idx, instruction) in program.iter().enumerate() {
+        if let Some(target) = usize::try_from(instruction.target(idx))
+            .ok()
+            .filter(|target| *target <= program.len())
+        {
+            predecessors[target].push(idx);
+        }
+    }
+
+    let mut terminating = vec![false; program.len() + 1];
+    terminating[program.len()] = true;
+    let mut queue = VecDeque::from([program.len()]);
+    while let Some(idx) = queue.pop_front() {
+        for predecessor in &predecessors[idx] {
+            if !terminating[*predecessor] {
+                terminating[*predecessor] = true;
+                queue.push_back(*predecessor);
+            }
+        }
+    }
+
+    terminating
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse_input,
+        vm::{Ending, Vm},
+        DATA,
+    };
+    use anyhow::Result;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_cfg_testdata() -> Result<()> {
+        let program = parse_input(TESTDATA)?;
+        let cfg = Cfg::new(&program);
+
+        assert_eq!(
+            cfg.blocks(),
+            [
+                Block {
+                    start: 0,
+                    end: 1,
+                    next: Exit::Block(1)
+                },
+                Block {
+                    start: 1,
+                    end: 3,
+                    next: Exit::Block(4)
+                },
+                Block {
+                    start: 3,
+                    end: 5,
+                    next: Exit::Block(1)
+                },
+                Block {
+                    start: 5,
+                    end: 6,
+                    next: Exit::Block(4)
+                },
+                Block {
+                    start: 6,
+                    end: 8,
+                    next: Exit::Block(2)
+                },
+                Block {
+                    start: 8,
+                    end: 9,
+                    next: Exit::Terminate
+                },
+            ]
+        );
+        assert_eq!(cfg.path(), [0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(cfg.path_end(), PathEnd::Loop(1));
+        assert_eq!(cfg.loops(), vec![vec![1, 4, 2]]);
+        assert_eq!(cfg.unreachable(), vec![5, 8]);
+        assert_eq!(cfg.terminating(), vec![8]);
+        assert!(!cfg.can_terminate(0));
+        assert_eq!(cfg.looping_acc(), Some(5));
+        assert_eq!(cfg.repair(), Some((7, 8)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cfg_matches_vm() -> Result<()> {
+        for data in [TESTDATA, DATA] {
+            let program = parse_input(data)?;
+            let cfg = Cfg::new(&program);
+
+            assert_eq!(
+                cfg.looping_acc().map(Ending::Looped),
+                Some(Vm::new(&program).run())
+            );
+            let (idx, acc) = cfg.repair().unwrap_or_default();
+            let mut repaired = program.clone();
+            if let Some(swapped) = program[idx].swapped() {
+                repaired[idx] = swapped;
+            }
+            assert_eq!(Vm::new(&repaired).run(), Ending::Terminated(acc));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cfg_dot_testdata() -> Result<()> {
+        let program = parse_input(TESTDATA)?;
+        let dot = Cfg::new(&program).to_dot();
+
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    b1 [label=\"1: acc +1\\l2: jmp +4\\l\"];\n"));
+        assert!(dot.contains("    b3 [label=\"5: acc -99\\l\", style=dashed];\n"));
+        assert!(dot.contains("    b4 -> b2;\n"));
+        assert!(dot.contains("    b5 -> exit;\n"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{
+    vm::{Ending, Registers, Vm},
+    Instruction,
+};
+use anyhow::{Error, Result};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    io::Write,
+};
+
+pub struct Debugger<'a> {
+    program: &'a [Instruction],
+    vm: Vm<'a, Instruction>,
+    breakpoints: BTreeSet<usize>,
+    watch_acc: bool,
+    history: VecDeque<(usize, Instruction, i32)>,
+    history_length: usize,
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Stepped(usize, Instruction),
+    Breakpoint(usize),
+    Watch { old: i32, new: i32 },
+    Halted(Ending),
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a [Instruction], history_length: usize) -> Self {
+        Self {
+            program,
+            vm: Vm::new(program),
+            breakpoints: BTreeSet::new(),
+            watch_acc: false,
+            history: VecDeque::with_capacity(history_length),
+            history_length,
+        }
+    }
+
+    pub fn execute(&mut self, command: &str) -> Result<String> {
+        let mut words = command.split_whitespace();
+        let output = match (words.next(), words.next()) {
+            (None, _) => String::new(),
+            (Some("step" | "s"), count) => {
+                let count = count.map_or(Ok(1), str::parse::<usize>)?;
+                let mut output = String::new();
+                for _ in 0..count {
+                    let event = self.step();
+                    output.push_str(&self.describe(&event));
+                    if !matches!(event, Event::Stepped(..)) {
+                        break;
+                    }
+                }
+                output
+            }
+            (Some("continue" | "c"), _) => {
+                // Always move once, so continuing from a breakpoint does not stop on it again.
+                let mut event = self.step();
+                while matches!(event, Event::Stepped(..)) {
+                    event = self.step();
+                }
+                self.describe(&event)
+            }
+            (Some("break" | "b"), Some(idx)) => {
+                let idx = idx.parse::<usize>()?;
+                self.breakpoints.insert(idx);
+                format!("Breakpoint at {idx}\n")
+            }
+            (Some("delete" | "d"), Some(idx)) => {
+                let idx = idx.parse::<usize>()?;
+                self.breakpoints.remove(&idx);
+                format!("Deleted breakpoint at {idx}\n")
+            }
+            (Some(command @ ("break" | "b" | "delete" | "d")), None) => {
+                return Err(Error::msg(format!("{command} needs an instruction index")));
+            }
+            (Some("watch" | "w"), _) => {
+                self.watch_acc = !self.watch_acc;
+                format!("Watching acc: {}\n", self.watch_acc)
+            }
+            (Some("registers" | "r"), _) => {
+                let Registers { acc, ip } = self.vm.registers();
+                format!("ip={ip} acc={acc}\n")
+            }
+            (Some("history" | "h"), _) => self.history(),
+            (Some(other), _) => return Err(Error::msg(format!("Unknown command {other}"))),
+        };
+
+        Ok(output)
+    }
+
+    fn step(&mut self) -> Event {
+        let before = self.vm.registers();
+        if let Some(ending) = self.vm.halted() {
+            return Event::Halted(ending);
+        }
+
+        let idx = before.ip as usize;
+        self.vm.step();
+        let after = self.vm.registers();
+        if self.history_length > 0 {
+            while self.history.len() >= self.history_length {
+                self.history.pop_front();
+            }
+            self.history.push_back((idx, self.program[idx], after.acc));
+        }
+
+        if let Some(ending) = self.vm.halted() {
+            Event::Halted(ending)
+        } else if self.watch_acc && before.acc != after.acc {
+            Event::Watch {
+                old: before.acc,
+                new: after.acc,
+            }
+        } else if usize::try_from(after.ip).is_ok_and(|ip| self.breakpoints.contains(&ip)) {
+            Event::Breakpoint(after.ip as usize)
+        } else {
+            Event::Stepped(idx, self.program[idx])
+        }
+    }
+
+    fn describe(&self, event: &Event) -> String {
+        let Registers { acc, ip } = self.vm.registers();
+        match event {
+            Event::Stepped(idx, instruction) => {
+                format!("{idx}: {instruction} -> ip={ip} acc={acc}\n")
+            }
+            Event::Breakpoint(idx) => format!("Breakpoint at {idx}, acc={acc}\n"),
+            Event::Watch { old, new } => format!("acc changed from {old} to {new}, ip={ip}\n"),
+            Event::Halted(Ending::Looped(acc)) if self.history.is_empty() => {
+                format!("Loop detected at {ip}, acc={acc}\n")
+            }
+            Event::Halted(Ending::Looped(acc)) => format!(
+                "Loop detected at {ip}, acc={acc}, last {} instructions:\n{}",
+                self.history.len(),
+                self.history()
+            ),
+            Event::Halted(Ending::Terminated(acc)) => format!("Terminated, acc={acc}\n"),
+            Event::Halted(Ending::OutOfBounds { acc, ip }) => {
+                format!("Jumped out of the program to {ip}, acc={acc}\n")
+            }
+        }
+    }
+
+    fn history(&self) -> String {
+        self.history
+            .iter()
+            .map(|(idx, instruction, acc)| format!("{idx:>5}: {instruction} (acc={acc})\n"))
+            .collect()
+    }
+}
+
+pub fn trace(program: &[Instruction], out: &mut impl Write) -> Result<Ending> {
+    let mut vm = Vm::new(program);
+    let mut step = 0;
+    loop {
+        let Registers { ip, .. } = vm.registers();
+        if let Some(ending) = vm.step() {
+            let (kind, acc) = match ending {
+                Ending::Looped(acc) => ("looped", acc),
+                Ending::Terminated(acc) => ("terminated", acc),
+                Ending::OutOfBounds { acc, .. } => ("out_of_bounds", acc),
+            };
+            writeln!(out, r#"{{"ending":"{kind}","ip":{ip},"acc":{acc}}}"#)?;
+            break Ok(ending);
+        }
+
+        let Registers { acc, ip: next } = vm.registers();
+        writeln!(
+            out,
+            r#"{{"step":{step},"ip":{ip},"instruction":"{}","acc":{acc},"next":{next}}}"#,
+            program[ip as usize]
+        )?;
+        step += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_debugger_testdata() -> Result<()> {
+        let program = parse_input(TESTDATA)?;
+        let mut debugger = Debugger::new(&program, 3);
+
+        assert_eq!(
+            debugger.execute("step 2")?,
+            "0: nop +0 -> ip=1 acc=0\n1: acc +1 -> ip=2 acc=1\n"
+        );
+        assert_eq!(debugger.execute("break 6")?, "Breakpoint at 6\n");
+        assert_eq!(debugger.execute("c")?, "Breakpoint at 6, acc=1\n");
+        assert_eq!(debugger.execute("watch")?, "Watching acc: true\n");
+        assert_eq!(debugger.execute("c")?, "acc changed from 1 to 2, ip=7\n");
+        assert_eq!(debugger.execute("r")?, "ip=7 acc=2\n");
+        assert_eq!(debugger.execute("w")?, "Watching acc: false\n");
+        assert_eq!(
+            debugger.execute("continue")?,
+            "Loop detected at 1, acc=5, last 3 instructions:\n    \
+             7: jmp -4 (acc=2)\n    \
+             3: acc +3 (acc=5)\n    \
+             4: jmp -3 (acc=5)\n"
+        );
+        assert!(debugger.execute("frobnicate").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debugger_without_history() -> Result<()> {
+        let program = parse_input(TESTDATA)?;
+        let mut debugger = Debugger::new(&program, 0);
+
+        assert_eq!(debugger.execute("s")?, "0: nop +0 -> ip=1 acc=0\n");
+        assert_eq!(debugger.execute("c")?, "Loop detected at 1, acc=5\n");
+        assert_eq!(debugger.execute("h")?, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trace_testdata() -> Result<()> {
+        let program = parse_input(TESTDATA)?;
+        let mut out = vec![];
+
+        assert_eq!(trace(&program, &mut out)?, Ending::Looped(5));
+        let out = String::from_utf8(out)?;
+        let lines = out.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            lines[0],
+            r#"{"step":0,"ip":0,"instruction":"nop +0","acc":0,"next":1}"#
+        );
+        assert_eq!(lines[7], r#"{"ending":"looped","ip":1,"acc":5}"#);
+
+        Ok(())
+    }
+}
@@ -1,105 +1,189 @@
-use anyhow::Result;
-use bit_vec::BitVec;
+use anyhow::{Error, Result};
+use asm::{assemble, disassemble};
+use cfg::Cfg;
+use debug::{trace, Debugger};
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{self, line_ending},
-    combinator::{map, value},
+    combinator::map,
     multi::separated_list1,
     sequence::preceded,
     IResult, Parser,
 };
+use repair::{repair, Repair};
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead, Write},
+};
+use vm::{Ending, Opcode, Registers, Vm};
+
+mod asm;
+mod cfg;
+mod debug;
+mod repair;
+mod vm;
 
 const DATA: &str = include_str!("input.txt");
-const DATA2: &str = include_str!("input2.txt");
 
 fn main() -> Result<()> {
-    let (took, result) = took::took(|| parse_input(DATA));
-    println!("Time spent parsing data: {}", took);
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let source = args.iter().skip_while(|arg| *arg != "--asm").nth(1);
+    let load = || match source {
+        Some(path) => assemble(&fs::read_to_string(path)?),
+        None => parse_input(DATA),
+    };
+
+    if args.iter().any(|arg| arg == "--cfg") {
+        print!("{}", cfg_report(&load()?));
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--cfg-dot") {
+        print!("{}", Cfg::new(&load()?).to_dot());
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--disassemble") {
+        print!("{}", disassemble(&load()?));
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--trace") {
+        trace(&load()?, &mut io::stdout().lock())?;
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--debug") {
+        let history = args
+            .iter()
+            .skip_while(|arg| *arg != "--history")
+            .nth(1)
+            .map_or(Ok(10), |n| n.parse::<usize>())?;
+        return debug(&load()?, history);
+    }
+
+    let (took, result) = took::took(load);
+    println!("Time spent parsing: {}", took);
     let input = result?;
 
     let (took, result) = took::took(|| part_one(&input));
-    println!("Result part one: {result}");
+    println!("Result part one: {}", result?);
     println!("Time spent: {took}");
 
-    let (took, result) = took::took(|| parse_input(DATA2));
-    println!("Time spent parsing data2: {}", took);
-    let input = result?;
-
     let (took, result) = took::took(|| part_two(&input));
-    println!("Result part two: {result}");
+    let repair = result?;
+    println!(
+        "Result part two: {} (changed instruction {} from {:?} to {:?})",
+        repair.acc, repair.index, repair.original, repair.replacement
+    );
     println!("Time spent: {took}");
 
     Ok(())
 }
 
-fn part_one(input: &[Instruction]) -> i32 {
-    match run_program(input) {
-        Ending::Visited(acc) => acc,
-        Ending::EndOfInstructions(_) => panic!("Should end on visited"),
+fn cfg_report(input: &[Instruction]) -> String {
+    let cfg = Cfg::new(input);
+    let mut report = format!("{} basic blocks\n", cfg.blocks().len());
+    report.push_str(&format!(
+        "Executed {} instructions, ending with {:?}\n",
+        cfg.path().len(),
+        cfg.path_end()
+    ));
+    for blocks in cfg.loops() {
+        let starts = blocks
+            .iter()
+            .map(|block| cfg.blocks()[*block].start.to_string())
+            .collect::<Vec<String>>();
+        report.push_str(&format!("Loop through {}\n", starts.join(" -> ")));
+    }
+    report.push_str(&format!("Unreachable: {:?}\n", cfg.unreachable()));
+    report.push_str(&format!("Can terminate: {:?}\n", cfg.terminating()));
+    if let Some(acc) = cfg.looping_acc() {
+        report.push_str(&format!("Part one: {acc}\n"));
+    }
+    if let Some((idx, acc)) = cfg.repair() {
+        report.push_str(&format!("Part two: {acc} (swap instruction {idx})\n"));
     }
-}
 
-fn part_two(input: &[Instruction]) -> i32 {
-    let mut last_changed = 0;
-    let mut input2 = input.to_vec();
-    loop {
-        if let Ending::EndOfInstructions(acc) = run_program(&input2) {
-            break acc;
-        }
+    report
+}
 
-        input2 = input.to_vec();
-        let (index, _) = input2
-            .iter()
-            .enumerate()
-            .find(|(index, instruction)| {
-                index > &last_changed && matches!(instruction, Instruction::Jmp(_))
-            })
-            .unwrap();
-        last_changed = index;
-        input2[index] = Instruction::Nop;
-    }
-}
-
-fn run_program(input: &[Instruction]) -> Ending {
-    let mut visited = BitVec::from_elem(1024, false);
-    let mut acc = 0;
-    let mut index = 0;
-    loop {
-        if index >= input.len() {
-            break Ending::EndOfInstructions(acc);
+// Reads debugger commands from stdin, so a script can be piped in as well.
+fn debug(input: &[Instruction], history: usize) -> Result<()> {
+    let mut debugger = Debugger::new(input, history);
+    let mut stdout = io::stdout();
+    write!(stdout, "> ")?;
+    stdout.flush()?;
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if matches!(line.trim(), "quit" | "q") {
+            break;
         }
-        if visited[index] {
-            break Ending::Visited(acc);
-        }
-        visited.set(index, true);
-        match input[index] {
-            Instruction::Acc(val) => {
-                acc += val as i32;
-                index += 1;
-            }
-            Instruction::Jmp(val) => {
-                if val < 0 {
-                    index -= val.unsigned_abs() as usize
-                } else {
-                    index += val.unsigned_abs() as usize
-                }
-            }
-            Instruction::Nop => index += 1,
+        match debugger.execute(&line) {
+            Ok(output) => write!(stdout, "{output}")?,
+            Err(e) => writeln!(stdout, "Error: {e}")?,
         }
+        write!(stdout, "> ")?;
+        stdout.flush()?;
     }
+
+    Ok(())
 }
 
-enum Ending {
-    Visited(i32),
-    EndOfInstructions(i32),
+fn part_one(input: &[Instruction]) -> Result<i32> {
+    match Vm::new(input).run() {
+        Ending::Looped(acc) => Ok(acc),
+        ending => Err(Error::msg(format!("Should end on a loop, got {ending:?}"))),
+    }
 }
 
-#[derive(Clone, Debug)]
+fn part_two(input: &[Instruction]) -> Result<Repair> {
+    repair(input)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Instruction {
     Acc(i16),
     Jmp(i16),
-    Nop,
+    Nop(i16),
+}
+
+impl Instruction {
+    // Where execution continues after this instruction at `idx`.
+    pub fn target(&self, idx: usize) -> isize {
+        match self {
+            Instruction::Jmp(val) => idx as isize + *val as isize,
+            Instruction::Acc(_) | Instruction::Nop(_) => idx as isize + 1,
+        }
+    }
+
+    pub fn swapped(&self) -> Option<Instruction> {
+        match self {
+            Instruction::Jmp(val) => Some(Instruction::Nop(*val)),
+            Instruction::Nop(val) => Some(Instruction::Jmp(*val)),
+            Instruction::Acc(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Acc(val) => write!(f, "acc {val:+}"),
+            Instruction::Jmp(val) => write!(f, "jmp {val:+}"),
+            Instruction::Nop(val) => write!(f, "nop {val:+}"),
+        }
+    }
+}
+
+impl Opcode for Instruction {
+    fn execute(&self, registers: &mut Registers) {
+        match self {
+            Instruction::Acc(val) => {
+                registers.acc += *val as i32;
+                registers.ip += 1;
+            }
+            Instruction::Jmp(val) => registers.ip += *val as isize,
+            Instruction::Nop(_) => registers.ip += 1,
+        }
+    }
 }
 
 fn parse(input: &str) -> IResult<&str, Vec<Instruction>> {
@@ -125,7 +209,10 @@ fn parse_jmp(input: &str) -> IResult<&str, Instruction> {
 }
 
 fn parse_nop(input: &str) -> IResult<&str, Instruction> {
-    value(Instruction::Nop, preceded(tag("nop "), complete::i16)).parse(input)
+    map(preceded(tag("nop "), complete::i16), |val: i16| {
+        Instruction::Nop(val)
+    })
+    .parse(input)
 }
 
 fn parse_input(input: &'static str) -> Result<Vec<Instruction>> {
@@ -142,28 +229,44 @@ mod tests {
 
     #[test]
     fn test_part_one_testdata() -> Result<()> {
-        assert_eq!(part_one(&parse_input(TESTDATA)?), 5);
+        assert_eq!(part_one(&parse_input(TESTDATA)?)?, 5);
 
         Ok(())
     }
 
     #[test]
     fn test_part_one() -> Result<()> {
-        assert_eq!(part_one(&parse_input(DATA)?), 1723);
+        assert_eq!(part_one(&parse_input(DATA)?)?, 1723);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two_testdata() -> Result<()> {
-        assert_eq!(part_two(&parse_input(TESTDATA)?), 8);
+        assert_eq!(part_two(&parse_input(TESTDATA)?)?.acc, 8);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two() -> Result<()> {
-        assert_eq!(part_two(&parse_input(DATA)?), 846);
+        assert_eq!(part_two(&parse_input(DATA)?)?.acc, 846);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_keeps_nop_operand() -> Result<()> {
+        let input = parse_input("nop +5\njmp -3\nacc +0")?;
+
+        assert_eq!(
+            input,
+            vec![
+                Instruction::Nop(5),
+                Instruction::Jmp(-3),
+                Instruction::Acc(0)
+            ]
+        );
 
         Ok(())
     }
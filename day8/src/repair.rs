@@ -0,0 +1,113 @@
+use crate::{
+    cfg::{Cfg, PathEnd},
+    vm::{Ending, Vm},
+    Instruction,
+};
+use anyhow::{Error, Result};
+
+#[derive(Debug, PartialEq)]
+pub struct Repair {
+    pub index: usize,
+    pub original: Instruction,
+    pub replacement: Instruction,
+    pub acc: i32,
+}
+
+pub fn repair(program: &[Instruction]) -> Result<Repair> {
+    let cfg = Cfg::new(program);
+    if cfg.path_end() == PathEnd::Terminate {
+        return Err(Error::msg("Program already terminates"));
+    }
+    let (index, _) = cfg
+        .repair()
+        .ok_or_else(|| Error::msg("No single jmp/nop swap makes the program terminate"))?;
+
+    let replacement = program[index]
+        .swapped()
+        .ok_or_else(|| Error::msg(format!("Instruction {index} cannot be swapped")))?;
+    let mut repaired = program.to_vec();
+    repaired[index] = replacement;
+    match Vm::new(&repaired).run() {
+        Ending::Terminated(acc) => Ok(Repair {
+            index,
+            original: program[index],
+            replacement,
+            acc,
+        }),
+        ending => Err(Error::msg(format!(
+            "Swapping instruction {index} should terminate, got {ending:?}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    fn brute_force(program: &[Instruction]) -> Option<(usize, i32)> {
+        (0..program.len()).find_map(|idx| {
+            let mut changed = program.to_vec();
+            changed[idx] = program[idx].swapped()?;
+            match Vm::new(&changed).run() {
+                Ending::Terminated(acc) => Some((idx, acc)),
+                _ => None,
+            }
+        })
+    }
+
+    #[test]
+    fn test_repair_testdata() -> Result<()> {
+        let program = parse_input(TESTDATA)?;
+
+        assert_eq!(
+            repair(&program)?,
+            Repair {
+                index: 7,
+                original: Instruction::Jmp(-4),
+                replacement: Instruction::Nop(-4),
+                acc: 8
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repair_nop_to_jmp() -> Result<()> {
+        let program = parse_input("nop +3\nacc +1\njmp -2\nacc +5")?;
+
+        assert_eq!(brute_force(&program), Some((0, 5)));
+        assert_eq!(
+            repair(&program)?,
+            Repair {
+                index: 0,
+                original: Instruction::Nop(3),
+                replacement: Instruction::Jmp(3),
+                acc: 5
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repair_impossible() -> Result<()> {
+        assert!(repair(&parse_input("jmp +0\njmp -1")?).is_err());
+        assert!(repair(&parse_input("acc +1\nnop +0")?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repair_data() -> Result<()> {
+        let program = parse_input(crate::DATA)?;
+        let repair = repair(&program)?;
+
+        assert_eq!(brute_force(&program), Some((repair.index, repair.acc)));
+
+        Ok(())
+    }
+}
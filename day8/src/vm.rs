@@ -0,0 +1,114 @@
+use bit_vec::BitVec;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Registers {
+    pub acc: i32,
+    pub ip: isize,
+}
+
+pub trait Opcode {
+    fn execute(&self, registers: &mut Registers);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ending {
+    Looped(i32),
+    Terminated(i32),
+    OutOfBounds { acc: i32, ip: isize },
+}
+
+pub struct Vm<'a, O> {
+    program: &'a [O],
+    registers: Registers,
+    visited: BitVec,
+}
+
+impl<'a, O: Opcode> Vm<'a, O> {
+    pub fn new(program: &'a [O]) -> Self {
+        Self {
+            program,
+            registers: Registers::default(),
+            visited: BitVec::from_elem(program.len(), false),
+        }
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.registers
+    }
+
+    // Running the instruction right after the last one is a normal exit,
+    // jumping anywhere else outside the program is not.
+    pub fn halted(&self) -> Option<Ending> {
+        let Registers { acc, ip } = self.registers;
+        match usize::try_from(ip) {
+            Ok(idx) if idx == self.program.len() => Some(Ending::Terminated(acc)),
+            Ok(idx) if idx < self.program.len() => self.visited[idx].then_some(Ending::Looped(acc)),
+            _ => Some(Ending::OutOfBounds { acc, ip }),
+        }
+    }
+
+    pub fn step(&mut self) -> Option<Ending> {
+        if let Some(ending) = self.halted() {
+            return Some(ending);
+        }
+
+        let idx = self.registers.ip as usize;
+        self.visited.set(idx, true);
+        self.program[idx].execute(&mut self.registers);
+
+        None
+    }
+
+    pub fn run(&mut self) -> Ending {
+        loop {
+            if let Some(ending) = self.step() {
+                break ending;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An opcode set from outside the VM, to check that it does not depend on `Instruction`.
+    enum Extended {
+        Add(i32),
+        Double,
+        Jump(isize),
+    }
+
+    impl Opcode for Extended {
+        fn execute(&self, registers: &mut Registers) {
+            match self {
+                Extended::Add(val) => registers.acc += val,
+                Extended::Double => registers.acc *= 2,
+                Extended::Jump(offset) => {
+                    registers.ip += offset;
+                    return;
+                }
+            }
+            registers.ip += 1;
+        }
+    }
+
+    #[test]
+    fn test_custom_opcodes() {
+        let program = [Extended::Add(3), Extended::Double, Extended::Jump(1)];
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.step(), None);
+        assert_eq!(vm.registers(), Registers { acc: 3, ip: 1 });
+        assert_eq!(vm.run(), Ending::Terminated(6));
+
+        let program = [Extended::Add(1), Extended::Jump(-1)];
+        assert_eq!(Vm::new(&program).run(), Ending::Looped(1));
+
+        let program = [Extended::Jump(-1)];
+        assert_eq!(
+            Vm::new(&program).run(),
+            Ending::OutOfBounds { acc: 0, ip: -1 }
+        );
+    }
+}
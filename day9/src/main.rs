@@ -1,91 +1,163 @@
-use anyhow::Result;
-use itertools::Itertools;
+#![cfg_attr(test, feature(test))]
+
+use anyhow::{Error, Result};
 use nom::{
     character::complete::{self, line_ending},
     multi::separated_list1,
     IResult, Parser,
 };
+use std::{
+    env,
+    io::{self, Write},
+    ops::Range,
+};
+use window::Window;
+
+mod stream;
+mod window;
 
 const DATA: &str = include_str!("input.txt");
+const PREAMBLE: usize = 25;
 
 fn main() -> Result<()> {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let preamble = args
+        .iter()
+        .skip_while(|arg| *arg != "--preamble")
+        .nth(1)
+        .map_or(Ok(PREAMBLE), |n| n.parse::<usize>())?;
+
+    if args.iter().any(|arg| arg == "--stream") {
+        let mut out = io::stdout().lock();
+        let invalid = stream::validate(io::stdin().lock(), preamble, &mut out)?;
+        writeln!(out, "Invalid numbers: {invalid}")?;
+        return Ok(());
+    }
+
     let (took, result) = took::took(|| parse_input(DATA));
     println!("Time spent parsing: {}", took);
     let input = result?;
 
-    let (took, result) = took::took(|| part_one(&input, 25));
-    println!("Result part one: {result}");
+    let (took, result) = took::took(|| part_one(&input, preamble));
+    println!("Result part one: {}", result?);
     println!("Time spent: {took}");
 
-    let (took, result) = took::took(|| part_two(&input, 25));
-    println!("Result part two: {result}");
+    let (took, result) = took::took(|| part_two(&input, preamble));
+    println!("Result part two: {}", result?);
     println!("Time spent: {took}");
 
+    if args.iter().any(|arg| arg == "--invalid") {
+        for Invalid {
+            index,
+            value,
+            window,
+        } in invalid_numbers(&input, preamble)
+        {
+            println!("{index}: {value} is not a sum of two of {window:?}");
+        }
+    }
+    if let Some(target) = args.iter().skip_while(|arg| *arg != "--ranges").nth(1) {
+        let target = target.parse::<u64>()?;
+        for range in weakness_ranges(&input, target) {
+            let weakness = encryption_weakness(&input[range.clone()])?;
+            println!("{}..={}: {weakness}", range.start, range.end - 1);
+        }
+    }
+
     Ok(())
 }
 
-fn part_one(input: &[u32], preamble: usize) -> u32 {
-    let (_, result) = find_number(input, preamble);
+fn part_one(input: &[u64], preamble: usize) -> Result<u64> {
+    let (_, result) = find_number(input, preamble)
+        .ok_or_else(|| Error::msg("Every number is a sum of two of its preamble"))?;
 
-    *result
+    Ok(result)
 }
 
-fn part_two(input: &[u32], preamble: usize) -> u32 {
-    let (result_idx, result) = find_number(input, preamble);
-    (0..result_idx)
-        .rev()
-        .find_map(|top_idx| {
-            let (bottom_idx, sum) = find_idx_and_sum(input, top_idx, *result);
-            if sum == *result {
-                Some((bottom_idx, top_idx))
-            } else {
-                None
-            }
-        })
-        .map(|(bottom_idx, top_idx)| {
-            let (min, max) = input[bottom_idx..top_idx]
-                .iter()
-                .minmax()
-                .into_option()
-                .unwrap();
-            *min + *max
-        })
-        .unwrap()
+fn part_two(input: &[u64], preamble: usize) -> Result<u64> {
+    let result = part_one(input, preamble)?;
+    let range = find_weakness(input, result)
+        .ok_or_else(|| Error::msg(format!("No contiguous range adds up to {result}")))?;
+
+    encryption_weakness(&input[range])
 }
 
-fn find_idx_and_sum(input: &[u32], top_idx: usize, result: u32) -> (usize, u32) {
-    (0..top_idx)
-        .rev()
-        .try_fold((0, 0), |(_, sum), idx| {
-            let sum = sum + input[idx];
-            if sum >= result {
-                Err((idx, sum))
-            } else {
-                Ok((idx, sum))
-            }
-        })
-        .unwrap_err()
+// The sum of the smallest and largest number in the range.
+fn encryption_weakness(range: &[u64]) -> Result<u64> {
+    let min = range.iter().min();
+    let max = range.iter().max();
+    match (min, max) {
+        (Some(min), Some(max)) => min
+            .checked_add(*max)
+            .ok_or_else(|| Error::msg(format!("{min} + {max} overflows"))),
+        _ => Err(Error::msg("The range is empty")),
+    }
 }
 
-fn find_number(input: &[u32], preamble: usize) -> (usize, &u32) {
-    input
-        .iter()
-        .skip(preamble)
-        .enumerate()
-        .find(|(idx, x)| {
-            !input[*idx..(idx + preamble)]
-                .iter()
-                .permutations(2)
-                .any(|v| **x == v[0] + v[1])
+#[derive(Debug, PartialEq)]
+struct Invalid<'a> {
+    index: usize,
+    value: u64,
+    window: &'a [u64],
+}
+
+// Every number that is not the sum of two of the `preamble` numbers before it,
+// with the numbers it was checked against.
+fn invalid_numbers(input: &[u64], preamble: usize) -> impl Iterator<Item = Invalid<'_>> {
+    let mut window = Window::new(preamble);
+    input.iter().enumerate().filter_map(move |(index, value)| {
+        let invalid = window.is_full() && !window.has_pair(*value);
+        window.push(*value);
+        invalid.then(|| Invalid {
+            index,
+            value: *value,
+            window: &input[index - preamble..index],
         })
-        .unwrap()
+    })
 }
 
-fn parse(input: &str) -> IResult<&str, Vec<u32>> {
-    separated_list1(line_ending, complete::u32).parse(input)
+// The index and value of the first number that is not the sum of two of the
+// `preamble` numbers before it.
+fn find_number(input: &[u64], preamble: usize) -> Option<(usize, u64)> {
+    invalid_numbers(input, preamble)
+        .next()
+        .map(|invalid| (invalid.index, invalid.value))
 }
 
-fn parse_input(input: &'static str) -> Result<Vec<u32>> {
+// The first contiguous range of at least two numbers that adds up to `target`.
+fn find_weakness(input: &[u64], target: u64) -> Option<Range<usize>> {
+    weakness_ranges(input, target).next()
+}
+
+// Every contiguous range of at least two numbers that adds up to `target`, by
+// end and then by start. All numbers are non-negative, so for each end the
+// starts with a sum of `target` lie between `low` (the first start whose sum
+// is not above it) and `high` (the first start whose sum is below it), and
+// both only move forward.
+fn weakness_ranges(input: &[u64], target: u64) -> impl Iterator<Item = Range<usize>> + '_ {
+    let target = target as u128;
+    let (mut low, mut low_sum) = (0, 0u128);
+    let (mut high, mut high_sum) = (0, 0u128);
+    input.iter().enumerate().flat_map(move |(end, x)| {
+        low_sum += *x as u128;
+        high_sum += *x as u128;
+        while low_sum > target {
+            low_sum -= input[low] as u128;
+            low += 1;
+        }
+        while high <= end && high_sum >= target {
+            high_sum -= input[high] as u128;
+            high += 1;
+        }
+        (low..high.min(end)).map(move |start| start..end + 1)
+    })
+}
+
+fn parse(input: &str) -> IResult<&str, Vec<u64>> {
+    separated_list1(line_ending, complete::u64).parse(input)
+}
+
+fn parse_input(input: &'static str) -> Result<Vec<u64>> {
     let (_, input) = parse(input)?;
 
     Ok(input)
@@ -94,34 +166,219 @@ fn parse_input(input: &'static str) -> Result<Vec<u32>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
+    use test::Bencher;
+
+    extern crate test;
 
     const TESTDATA: &str = include_str!("test.txt");
 
     #[test]
     fn test_part_one_testdata() -> Result<()> {
-        assert_eq!(part_one(&parse_input(TESTDATA)?, 5), 127);
+        assert_eq!(part_one(&parse_input(TESTDATA)?, 5)?, 127);
 
         Ok(())
     }
 
     #[test]
     fn test_part_one() -> Result<()> {
-        assert_eq!(part_one(&parse_input(DATA)?, 25), 530627549);
+        assert_eq!(part_one(&parse_input(DATA)?, 25)?, 530627549);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two_testdata() -> Result<()> {
-        assert_eq!(part_two(&parse_input(TESTDATA)?, 5), 62);
+        assert_eq!(part_two(&parse_input(TESTDATA)?, 5)?, 62);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two() -> Result<()> {
-        assert_eq!(part_two(&parse_input(DATA)?, 25), 77730285);
+        assert_eq!(part_two(&parse_input(DATA)?, 25)?, 77730285);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_numbers_testdata() -> Result<()> {
+        let input = parse_input(TESTDATA)?;
+        let invalid = invalid_numbers(&input, 5).collect::<Vec<Invalid>>();
+
+        assert_eq!(
+            invalid,
+            vec![Invalid {
+                index: 14,
+                value: 127,
+                window: &[95, 102, 117, 150, 182]
+            }]
+        );
+        assert_eq!(
+            invalid_numbers(&[1, 2, 3, 5, 4, 9, 20], 2)
+                .map(|invalid| (invalid.index, invalid.value, invalid.window))
+                .collect::<Vec<(usize, u64, &[u64])>>(),
+            vec![(4, 4, &[3, 5][..]), (6, 20, &[4, 9][..])]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_weakness_ranges() -> Result<()> {
+        let input = parse_input(TESTDATA)?;
+
+        assert_eq!(weakness_ranges(&input, 127).collect::<Vec<_>>(), vec![2..6]);
+        assert_eq!(
+            weakness_ranges(&[1, 2, 3, 0, 3, 3], 6).collect::<Vec<_>>(),
+            vec![0..3, 0..4, 2..5, 3..6, 4..6]
+        );
+        assert_eq!(weakness_ranges(&[6, 0], 6).collect::<Vec<_>>(), vec![0..2]);
+        assert_eq!(weakness_ranges(&[0, 0], 0).collect::<Vec<_>>(), vec![0..2]);
+        assert_eq!(weakness_ranges(&[6], 6).next(), None);
 
         Ok(())
     }
+
+    #[test]
+    fn test_nothing_found() {
+        assert!(part_one(&[1, 2, 3, 5, 8], 2).is_err());
+        assert!(part_two(&[1, 2, 4, 9], 2).is_err());
+        assert!(encryption_weakness(&[]).is_err());
+    }
+
+    fn naive_find_number(input: &[u64], preamble: usize) -> Option<(usize, u64)> {
+        input
+            .iter()
+            .enumerate()
+            .skip(preamble)
+            .find(|(idx, x)| {
+                !input[(idx - preamble)..*idx]
+                    .iter()
+                    .permutations(2)
+                    .any(|v| **x == v[0] + v[1])
+            })
+            .map(|(idx, x)| (idx, *x))
+    }
+
+    fn naive_find_weakness(input: &[u64], top_idx: usize, target: u64) -> Option<Range<usize>> {
+        (0..top_idx).rev().find_map(|top_idx| {
+            let (bottom_idx, sum) = naive_idx_and_sum(input, top_idx, target);
+            (sum == target as u128 && top_idx - bottom_idx >= 2).then_some(bottom_idx..top_idx)
+        })
+    }
+
+    fn naive_idx_and_sum(input: &[u64], top_idx: usize, target: u64) -> (usize, u128) {
+        (0..top_idx)
+            .rev()
+            .try_fold((top_idx, 0), |(_, sum), idx| {
+                let sum = sum + input[idx] as u128;
+                if sum >= target as u128 {
+                    Err((idx, sum))
+                } else {
+                    Ok((idx, sum))
+                }
+            })
+            .unwrap_or_else(|found| found)
+    }
+
+    // Numbers from 1 to `bound`, scattered with a multiplicative hash of the index.
+    // Most of them cannot be written as a sum from the preamble once `bound` is
+    // large compared to the preamble.
+    fn synthetic_stream(len: usize, bound: u64) -> Vec<u64> {
+        (0..len as u64)
+            .map(|idx| 1 + (idx.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) % bound)
+            .collect()
+    }
+
+    fn naive_invalid_numbers(input: &[u64], preamble: usize) -> Vec<usize> {
+        (preamble..input.len())
+            .filter(|idx| naive_find_number(&input[idx - preamble..=*idx], preamble).is_some())
+            .collect()
+    }
+
+    // Every range of at least two numbers, by end and then by start.
+    fn naive_weakness_ranges(input: &[u64], target: u64) -> Vec<Range<usize>> {
+        (0..input.len())
+            .flat_map(|end| (0..end).map(move |start| start..end + 1))
+            .filter(|range| input[range.clone()].iter().sum::<u64>() == target)
+            .collect()
+    }
+
+    #[test]
+    fn test_find_number_synthetic() {
+        let input = synthetic_stream(2_000, 200);
+        for preamble in [2, 5, 25] {
+            assert_eq!(
+                find_number(&input, preamble),
+                naive_find_number(&input, preamble)
+            );
+            assert_eq!(
+                invalid_numbers(&input, preamble)
+                    .map(|invalid| invalid.index)
+                    .collect::<Vec<usize>>(),
+                naive_invalid_numbers(&input, preamble)
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_weakness_synthetic() {
+        let input = synthetic_stream(500, 200);
+        for (start, end) in [(0, 2), (100, 130), (300, 302), (100, 490)] {
+            let target = input[start..end].iter().sum::<u64>();
+            let ranges = naive_weakness_ranges(&input, target);
+
+            assert!(ranges.contains(&(start..end)));
+            assert_eq!(weakness_ranges(&input, target).collect::<Vec<_>>(), ranges);
+            assert_eq!(find_weakness(&input, target), ranges.first().cloned());
+            // The old search starts from the end, so it finds the last range.
+            assert_eq!(
+                naive_find_weakness(&input, input.len(), target),
+                ranges.last().cloned()
+            );
+        }
+        assert_eq!(find_weakness(&[1, 2, 3], 2), None);
+        assert_eq!(find_weakness(&[3, 2, 1], 6), Some(0..3));
+        assert_eq!(find_weakness(&[], 0), None);
+    }
+
+    #[bench]
+    fn bench_invalid_numbers_window_1m(b: &mut Bencher) {
+        let input = synthetic_stream(1_000_000, 1_000);
+        b.iter(|| invalid_numbers(&input, 25).count());
+    }
+
+    #[bench]
+    fn bench_invalid_numbers_window_10k(b: &mut Bencher) {
+        let input = synthetic_stream(10_000, 1_000);
+        b.iter(|| invalid_numbers(&input, 25).count());
+    }
+
+    #[bench]
+    fn bench_invalid_numbers_naive_10k(b: &mut Bencher) {
+        let input = synthetic_stream(10_000, 1_000);
+        b.iter(|| naive_invalid_numbers(&input, 25));
+    }
+
+    #[bench]
+    fn bench_find_weakness_two_pointer_1m(b: &mut Bencher) {
+        let input = synthetic_stream(1_000_000, 1_000);
+        let target = input[400_000..900_000].iter().sum::<u64>();
+        b.iter(|| find_weakness(&input, target));
+    }
+
+    #[bench]
+    fn bench_find_weakness_two_pointer_10k(b: &mut Bencher) {
+        let input = synthetic_stream(10_000, 1_000);
+        let target = input[4_000..9_000].iter().sum::<u64>();
+        b.iter(|| find_weakness(&input, target));
+    }
+
+    #[bench]
+    fn bench_find_weakness_naive_10k(b: &mut Bencher) {
+        let input = synthetic_stream(10_000, 1_000);
+        let target = input[4_000..9_000].iter().sum::<u64>();
+        b.iter(|| naive_find_weakness(&input, input.len(), target));
+    }
 }
@@ -0,0 +1,74 @@
+use crate::window::Window;
+use anyhow::{Error, Result};
+use std::io::{BufRead, Write};
+
+// Reads whitespace separated numbers line by line, writing every invalid number
+// as `index: value` as soon as it is read. Only the last `preamble` numbers are
+// kept. Returns the number of invalid numbers.
+pub fn validate(input: impl BufRead, preamble: usize, out: &mut impl Write) -> Result<usize> {
+    let mut window = Window::new(preamble);
+    let mut index = 0;
+    let mut invalid = 0;
+    for (line_idx, line) in input.lines().enumerate() {
+        for word in line?.split_whitespace() {
+            let value = word
+                .parse::<u64>()
+                .map_err(|_| Error::msg(format!("Line {}: cannot parse {word:?}", line_idx + 1)))?;
+            if window.is_full() && !window.has_pair(value) {
+                writeln!(out, "{index}: {value}")?;
+                out.flush()?;
+                invalid += 1;
+            }
+            window.push(value);
+            index += 1;
+        }
+    }
+
+    Ok(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{invalid_numbers, parse_input};
+
+    const TESTDATA: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_validate_testdata() -> Result<()> {
+        let mut out = vec![];
+
+        assert_eq!(validate(TESTDATA.as_bytes(), 5, &mut out)?, 1);
+        assert_eq!(String::from_utf8(out)?, "14: 127\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_matches_invalid_numbers() -> Result<()> {
+        let input = parse_input(TESTDATA)?;
+        for preamble in 1..6 {
+            let mut out = vec![];
+            validate(TESTDATA.as_bytes(), preamble, &mut out)?;
+
+            let expected = invalid_numbers(&input, preamble)
+                .map(|invalid| format!("{}: {}\n", invalid.index, invalid.value))
+                .collect::<String>();
+            assert_eq!(String::from_utf8(out)?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_u64() -> Result<()> {
+        let mut out = vec![];
+        let input = "5000000000 6000000000\n\n11000000000 12000000000\n";
+
+        assert_eq!(validate(input.as_bytes(), 2, &mut out)?, 1);
+        assert_eq!(String::from_utf8(out)?, "3: 12000000000\n");
+        assert!(validate("1 2\nthree".as_bytes(), 2, &mut vec![]).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::{HashMap, VecDeque};
+
+// The last `capacity` numbers, with a count per value so that checking for a
+// pair only needs one lookup per number in the window.
+pub struct Window {
+    capacity: usize,
+    values: VecDeque<u64>,
+    counts: HashMap<u64, usize>,
+}
+
+impl Window {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            values: VecDeque::with_capacity(capacity + 1),
+            counts: HashMap::with_capacity(capacity + 1),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.capacity
+    }
+
+    pub fn push(&mut self, value: u64) {
+        self.values.push_back(value);
+        *self.counts.entry(value).or_insert(0) += 1;
+        if self.values.len() > self.capacity
+            && let Some(oldest) = self.values.pop_front()
+            && let Some(count) = self.counts.get_mut(&oldest)
+        {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&oldest);
+            }
+        }
+    }
+
+    // Whether two numbers at different positions in the window add up to `value`.
+    pub fn has_pair(&self, value: u64) -> bool {
+        self.values.iter().any(|a| {
+            value.checked_sub(*a).is_some_and(|b| {
+                let needed = if b == *a { 2 } else { 1 };
+                self.counts.get(&b).is_some_and(|count| *count >= needed)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window() {
+        let mut window = Window::new(3);
+        for value in [1, 2, 3] {
+            assert!(!window.is_full());
+            window.push(value);
+        }
+
+        assert!(window.is_full());
+        assert!(window.has_pair(5));
+        assert!(window.has_pair(3));
+        assert!(!window.has_pair(2));
+        assert!(!window.has_pair(6));
+
+        window.push(3);
+        assert!(window.has_pair(6));
+        assert!(!window.has_pair(3));
+    }
+}